@@ -0,0 +1,36 @@
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::{Event, HtmlInputElement};
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub label: String,
+    pub checked: bool,
+    pub on_change: Callback<bool>,
+}
+
+fn get_checked_from_event(e: Event) -> bool {
+    let event_target = e.target().unwrap_throw();
+    let target: HtmlInputElement = event_target.dyn_into().unwrap_throw();
+    target.checked()
+}
+
+#[function_component(Checkbox)]
+pub fn checkbox(props: &Props) -> Html {
+    let Props {
+        label,
+        checked,
+        on_change,
+    } = props.clone();
+
+    let onchange = Callback::from(move |event: Event| {
+        on_change.emit(get_checked_from_event(event));
+    });
+
+    html! {
+        <label>
+        <input type="checkbox" {checked} {onchange} />
+        { label }
+        </label>
+    }
+}
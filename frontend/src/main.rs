@@ -1,4 +1,5 @@
 use crate::image_future::ImageFuture;
+use crate::progress::fetch_with_progress;
 use gloo::utils::document;
 use gloo_net::http::Request;
 use log::info;
@@ -6,9 +7,12 @@ use yew::platform::spawn_local;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+mod checkbox;
 mod image_future;
+mod progress;
 mod text_input;
 
+use crate::checkbox::Checkbox;
 use crate::text_input::TextInput;
 
 #[derive(Clone, Routable, PartialEq)]
@@ -49,6 +53,10 @@ fn app() -> Html {
 #[derive(Clone, PartialEq, Properties)]
 struct PlotProps {
     name: String,
+    /// Draw a trend line through each season
+    season_trends: bool,
+    /// Draw a trend line through the whole show
+    show_trend: bool,
 }
 
 #[function_component(Plot)]
@@ -65,18 +73,36 @@ fn plot(props: &PlotProps) -> Html {
         let contents = contents.clone();
         let is_loaded = is_loaded.clone();
         use_effect_with_deps(
-            move |name| {
+            move |(name, season_trends, show_trend)| {
                 // show loader
                 let div: web_sys::Element = document().create_element("div").unwrap();
                 div.set_inner_html("Loading...");
                 let node: web_sys::Node = div.into();
                 contents.set(Html::VRef(node));
 
-                let name = name.clone();
+                let (name, season_trends, show_trend) = (name.clone(), *season_trends, *show_trend);
                 info!("fetch image: {}", name);
                 spawn_local(async move {
                     let name = urlencoding::encode(&name);
-                    let url = format!("/api/image?name={}", name);
+
+                    let on_status = {
+                        let contents = contents.clone();
+                        move |status: String| {
+                            let div: web_sys::Element = document().create_element("div").unwrap();
+                            div.set_text_content(Some(&status));
+                            contents.set(Html::VRef(div.into()));
+                        }
+                    };
+                    let url = format!("/api/progress?name={}", name);
+                    if let Err(err) = fetch_with_progress(&url, &on_status).await {
+                        on_status(err);
+                        return;
+                    }
+
+                    let url = format!(
+                        "/api/image?name={}&season_trends={}&show_trend={}",
+                        name, season_trends, show_trend
+                    );
                     let image = ImageFuture::new(&url).await.unwrap();
                     info!("done");
                     let node: web_sys::Node = image.into();
@@ -84,7 +110,7 @@ fn plot(props: &PlotProps) -> Html {
                     is_loaded.set(true);
                 });
             },
-            props.name.clone(),
+            (props.name.clone(), props.season_trends, props.show_trend),
         );
     }
 
@@ -96,6 +122,8 @@ fn plot(props: &PlotProps) -> Html {
 fn search() -> Html {
     let search = use_state(|| "".to_string());
     let name = use_state(|| -> Option<String> { None });
+    let season_trends = use_state(|| false);
+    let show_trend = use_state(|| false);
 
     let on_change = {
         let search = search.clone();
@@ -112,7 +140,17 @@ fn search() -> Html {
         })
     };
 
+    let on_season_trends = {
+        let season_trends = season_trends.clone();
+        Callback::from(move |checked| season_trends.set(checked))
+    };
+    let on_show_trend = {
+        let show_trend = show_trend.clone();
+        Callback::from(move |checked| show_trend.set(checked))
+    };
+
     let name = (*name).clone();
+    let (season_trends, show_trend) = (*season_trends, *show_trend);
 
     html! {
         <div>
@@ -120,8 +158,10 @@ fn search() -> Html {
         <form method="post" {onsubmit}>
         <TextInput value={(*search).clone()} on_change={on_change} />
         </form>
+        <Checkbox label="Season trends" checked={season_trends} on_change={on_season_trends} />
+        <Checkbox label="Show trend" checked={show_trend} on_change={on_show_trend} />
         if let Some(name) = name {
-            <Plot {name} />
+            <Plot {name} {season_trends} {show_trend} />
         }
         </div>
     }
@@ -0,0 +1,63 @@
+use futures::stream::{select_all, StreamExt};
+use gloo_net::eventsource::futures::EventSource;
+use serde::Deserialize;
+
+/// Progress events sent by `/api/progress`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Progress {
+    Resolved { title: String },
+    Seasons { count: usize },
+    SeasonDone {},
+}
+
+/// Fetch a TV show on the server, calling `on_status` with a status line as it progresses
+///
+/// Resolves once the show is ready to be loaded from `/api/image`.
+pub async fn fetch_with_progress(url: &str, on_status: impl Fn(String)) -> Result<(), String> {
+    let mut source = EventSource::new(url).map_err(|e| e.to_string())?;
+    let subscriptions = ["progress", "done", "failed"]
+        .into_iter()
+        .map(|event| source.subscribe(event))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut events = select_all(subscriptions);
+
+    let mut title = String::from("show");
+    let mut seasons = 0;
+    let mut done = 0;
+
+    while let Some(event) = events.next().await {
+        let (kind, message) = event.map_err(|e| e.to_string())?;
+        let data = message.data().as_string().unwrap_or_default();
+
+        match kind.as_str() {
+            "progress" => {
+                match serde_json::from_str(&data) {
+                    Ok(Progress::Resolved { title: t }) => title = t,
+                    Ok(Progress::Seasons { count }) => {
+                        seasons = count;
+                        done = 0;
+                    }
+                    Ok(Progress::SeasonDone {}) => done += 1,
+                    Err(_) => continue,
+                }
+                if seasons > 0 {
+                    on_status(format!("Loading {}: {}/{} seasons", title, done, seasons));
+                } else {
+                    on_status(format!("Loading {}...", title));
+                }
+            }
+            "done" => {
+                source.close();
+                return Ok(());
+            }
+            _ => {
+                source.close();
+                return Err(serde_json::from_str(&data).unwrap_or(data));
+            }
+        }
+    }
+
+    Err("connection closed".to_string())
+}
@@ -0,0 +1,225 @@
+//! Change-point detection, finding where a show's ratings shifted for good
+//!
+//! Uses binary segmentation: the split that best separates a stretch of episodes into two
+//! different means is kept when a permutation test says it is unlikely to be chance, and both
+//! halves are searched again.
+
+use crate::model::{SeasonId, Show};
+use crate::plot;
+use serde::Serialize;
+
+/// Settings for [`detect`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Minimum fraction of shuffled ratings that must split worse than the real ones
+    pub min_confidence: f32,
+    /// Minimum number of rated episodes on each side of a change
+    pub min_episodes: usize,
+    /// Minimum difference between the mean rating before and after
+    pub min_shift: f32,
+    /// Number of shuffles in the permutation test
+    pub permutations: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            min_confidence: 0.95,
+            min_episodes: 5,
+            min_shift: 0.3,
+            permutations: 1000,
+        }
+    }
+}
+
+/// An episode where the ratings shifted up or down
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangePoint {
+    /// Position of the first episode after the change among the episodes of its season
+    pub position: usize,
+    pub season: SeasonId,
+    pub number: Option<u32>,
+    pub title: String,
+    /// Mean rating of the episodes before the change, back to the previous change
+    pub before: f32,
+    /// Mean rating of the episodes from the change, up to the next change
+    pub after: f32,
+    /// Fraction of shuffled ratings without as clear a split, from 0 to 1
+    pub confidence: f32,
+}
+
+impl ChangePoint {
+    /// Change in mean rating, negative for a drop
+    pub fn shift(&self) -> f32 {
+        self.after - self.before
+    }
+
+    /// Marker for the chart, e.g. "S5E3 -1.2 (99%)"
+    pub fn marker(&self) -> plot::Marker {
+        let number = self.number.map_or("?".to_string(), |n| n.to_string());
+        plot::Marker {
+            season: self.season,
+            episode: self.position,
+            label: format!(
+                "S{}E{} {:+.1} ({:.0}%)",
+                self.season,
+                number,
+                self.shift(),
+                self.confidence * 100.0
+            ),
+        }
+    }
+}
+
+/// Find the significant, sustained shifts in the ratings of a show, in airing order
+pub fn detect(show: &Show, options: &Options) -> Vec<ChangePoint> {
+    let episodes: Vec<_> = show
+        .seasons
+        .iter()
+        .flat_map(|season| {
+            let id = season.season;
+            season
+                .episodes
+                .iter()
+                .enumerate()
+                .map(move |(i, e)| (id, i, e))
+        })
+        .collect();
+    // Positions in `episodes` and ratings of the rated ones
+    let (positions, ratings): (Vec<_>, Vec<_>) = episodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, _, e))| Some((i, e.rating?)))
+        .unzip();
+
+    let mut splits = Vec::new();
+    let mut rng = XorShift::new();
+    segment(&ratings, 0, options, &mut rng, &mut splits);
+    splits.sort_by_key(|split| split.at);
+
+    splits
+        .into_iter()
+        .map(|split| {
+            let (season, position, episode) = episodes[positions[split.at]];
+            ChangePoint {
+                position,
+                season,
+                number: episode.number,
+                title: episode.title.clone(),
+                before: split.before,
+                after: split.after,
+                confidence: split.confidence,
+            }
+        })
+        .collect()
+}
+
+/// The biggest significant drop in ratings, if there is one
+pub fn jumped_the_shark(show: &Show) -> Option<ChangePoint> {
+    detect(show, &Options::default())
+        .into_iter()
+        .filter(|c| c.shift() < 0.0)
+        .min_by(|a, b| a.shift().total_cmp(&b.shift()))
+}
+
+/// A split of the rated episodes, `at` being the first one after it
+struct Split {
+    at: usize,
+    before: f32,
+    after: f32,
+    confidence: f32,
+}
+
+/// Split `values` at its most significant change and search both halves again
+fn segment(
+    values: &[f32],
+    offset: usize,
+    options: &Options,
+    rng: &mut XorShift,
+    splits: &mut Vec<Split>,
+) {
+    let min = options.min_episodes.max(1);
+    let Some((at, score)) = best_split(values, min) else {
+        return;
+    };
+
+    let (left, right) = values.split_at(at);
+    let before = left.iter().sum::<f32>() / left.len() as f32;
+    let after = right.iter().sum::<f32>() / right.len() as f32;
+    if (after - before).abs() < options.min_shift {
+        return;
+    }
+
+    let mut shuffled = values.to_vec();
+    let worse = (0..options.permutations)
+        .filter(|_| {
+            rng.shuffle(&mut shuffled);
+            best_split(&shuffled, min).is_none_or(|(_, s)| s < score)
+        })
+        .count();
+    let confidence = worse as f32 / options.permutations.max(1) as f32;
+    if confidence < options.min_confidence {
+        return;
+    }
+
+    splits.push(Split {
+        at: offset + at,
+        before,
+        after,
+        confidence,
+    });
+    segment(left, offset, options, rng, splits);
+    segment(right, offset + at, options, rng, splits);
+}
+
+/// The split with the largest between-group difference, weighted by the group sizes
+///
+/// Maximizing this is the same as minimizing the squared error of two constant means.
+fn best_split(values: &[f32], min: usize) -> Option<(usize, f32)> {
+    let n = values.len();
+    if n < 2 * min {
+        return None;
+    }
+    let total: f32 = values.iter().sum();
+
+    let mut sum = 0.0;
+    let mut best: Option<(usize, f32)> = None;
+    for (i, value) in values.iter().enumerate().take(n - min) {
+        sum += value;
+        let at = i + 1;
+        if at < min {
+            continue;
+        }
+        let (left, right) = (at as f32, (n - at) as f32);
+        let diff = sum / left - (total - sum) / right;
+        let score = diff.abs() * (left * right / n as f32).sqrt();
+        if best.is_none_or(|(_, b)| score > b) {
+            best = Some((at, score));
+        }
+    }
+    best
+}
+
+/// Small deterministic random number generator, so the same ratings give the same result
+struct XorShift(u64);
+
+impl XorShift {
+    fn new() -> XorShift {
+        XorShift(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Fisher-Yates shuffle
+    fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            values.swap(i, j);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::tvshow::Error;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, RETRY_AFTER};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+const USER_AGENT: &str = concat!("imrs/", env!("CARGO_PKG_VERSION"));
+const BASE_URL: &str = "https://www.imdb.com";
+const GRAPHQL_URL: &str = "https://graphql.imdb.com/";
+
+/// Shared HTTP client for imdb.com
+///
+/// Reuses connections, retries rate limited and failed requests with exponential backoff and
+/// limits the number of requests in flight across all clones.
+#[derive(Debug, Clone)]
+pub struct ImdbClient {
+    client: reqwest::Client,
+    base_url: String,
+    graphql_url: String,
+    permits: Arc<Semaphore>,
+    max_retries: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    max_retry_after: Duration,
+}
+
+impl Default for ImdbClient {
+    fn default() -> Self {
+        ImdbClient::new()
+    }
+}
+
+impl ImdbClient {
+    pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+    pub const MAX_RETRIES: u32 = 3;
+    /// Longest delay between retries, however many retries there were before
+    pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+    /// Longest `Retry-After` to wait for, longer ones fail right away instead of stalling a lookup
+    pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+    pub fn new() -> ImdbClient {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en"));
+
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .default_headers(headers)
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("failed to build HTTP client");
+
+        ImdbClient {
+            client,
+            base_url: BASE_URL.to_string(),
+            graphql_url: GRAPHQL_URL.to_string(),
+            permits: Arc::new(Semaphore::new(Self::MAX_CONCURRENT_REQUESTS)),
+            max_retries: Self::MAX_RETRIES,
+            backoff: Duration::from_millis(500),
+            max_backoff: Self::MAX_BACKOFF,
+            max_retry_after: Self::MAX_RETRY_AFTER,
+        }
+    }
+
+    /// Send requests somewhere else than imdb.com, e.g. a local stand-in server
+    pub fn with_urls(mut self, base_url: &str, graphql_url: &str) -> ImdbClient {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self.graphql_url = graphql_url.to_string();
+        self
+    }
+
+    /// Full URL for a path on the website, e.g. `/title/tt0903747/`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Limit the number of requests in flight at the same time
+    pub fn with_max_concurrent_requests(mut self, max: usize) -> ImdbClient {
+        self.permits = Arc::new(Semaphore::new(max));
+        self
+    }
+
+    /// Number of retries, and the delay before the first one which doubles on each retry
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> ImdbClient {
+        self.max_retries = max_retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Longest delay between retries, the doubling stops there
+    pub fn with_max_backoff(mut self, max: Duration) -> ImdbClient {
+        self.max_backoff = max;
+        self
+    }
+
+    /// Longest `Retry-After` to wait for before retrying
+    pub fn with_max_retry_after(mut self, max: Duration) -> ImdbClient {
+        self.max_retry_after = max;
+        self
+    }
+
+    /// GET a page and return the body
+    pub async fn get_text(&self, url: &str) -> Result<String, Error> {
+        self.send(url, || self.client.get(url)).await
+    }
+
+    /// POST a GraphQL query and return the JSON response body
+    pub async fn graphql<T: Serialize>(&self, query: &str, variables: &T) -> Result<String, Error> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let url = &self.graphql_url;
+        self.send(url, || self.client.post(url).json(&body)).await
+    }
+
+    async fn send(&self, url: &str, request: impl Fn() -> RequestBuilder) -> Result<String, Error> {
+        let mut attempt = 0;
+        loop {
+            let permit = self.permits.acquire().await.expect("semaphore closed");
+            let response = request().send().await;
+
+            let delay = match response {
+                Ok(response) if response.status().is_success() => {
+                    return Ok(response.text().await?);
+                }
+                Ok(response) if attempt < self.max_retries && is_retryable(response.status()) => {
+                    warn!("{} returned {}", url, response.status());
+                    match retry_after(&response) {
+                        Some(delay) if delay > self.max_retry_after => {
+                            warn!("{} asked to retry after {:?}, giving up", url, delay);
+                            return Err(Error::Status {
+                                url: url.to_string(),
+                                status: response.status(),
+                            });
+                        }
+                        Some(delay) => delay,
+                        None => self.backoff(attempt),
+                    }
+                }
+                Ok(response) => {
+                    return Err(Error::Status {
+                        url: url.to_string(),
+                        status: response.status(),
+                    });
+                }
+                Err(err)
+                    if attempt < self.max_retries && (err.is_timeout() || err.is_connect()) =>
+                {
+                    warn!("{} failed: {}", url, err);
+                    self.backoff(attempt)
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            // Let other requests through while waiting
+            drop(permit);
+
+            attempt += 1;
+            info!("retry {} of {} in {:?}", attempt, url, delay);
+            sleep(delay).await;
+        }
+    }
+
+    /// Delay before retry number `attempt + 1`, doubling each time up to the maximum
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff
+            .checked_mul(2u32.saturating_pow(attempt))
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The delay asked for by a `Retry-After: <seconds>` header
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.parse().ok().map(Duration::from_secs)
+}
@@ -0,0 +1,88 @@
+//! Episode ratings grouped by the directors or writers credited on them
+
+use crate::model::{Episode, Show};
+use crate::stats;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Which credits to group episodes by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Director,
+    Writer,
+}
+
+impl Role {
+    fn names<'a>(&self, episode: &'a Episode) -> &'a [String] {
+        match self {
+            Role::Director => &episode.directors,
+            Role::Writer => &episode.writers,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "director" | "directors" => Ok(Role::Director),
+            "writer" | "writers" => Ok(Role::Writer),
+            _ => Err(format!("unknown role {:?}, expected director or writer", s)),
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Director => write!(f, "director"),
+            Role::Writer => write!(f, "writer"),
+        }
+    }
+}
+
+/// The rated episodes a person is credited on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub mean: f32,
+    pub median: f32,
+    /// Ratings of the episodes, in the order they were aired
+    pub ratings: Vec<f32>,
+}
+
+impl Group {
+    fn new(name: String, ratings: Vec<f32>) -> Group {
+        Group {
+            name,
+            mean: stats::mean(&ratings).unwrap_or_default(),
+            median: stats::median(&ratings).unwrap_or_default(),
+            ratings,
+        }
+    }
+}
+
+/// Group the rated episodes of a show by credited person, best mean rating first
+///
+/// Episodes with several directors or writers count for each of them. The crew has to be
+/// fetched first, see [`crate::source::RatingsSource::fetch_crew`].
+pub fn group_by(show: &Show, role: Role) -> Vec<Group> {
+    let mut ratings: BTreeMap<&str, Vec<f32>> = BTreeMap::new();
+    for episode in show.episodes() {
+        if let Some(rating) = episode.rating {
+            for name in role.names(episode) {
+                ratings.entry(name).or_default().push(rating);
+            }
+        }
+    }
+
+    let mut groups: Vec<_> = ratings
+        .into_iter()
+        .map(|(name, ratings)| Group::new(name.to_string(), ratings))
+        .collect();
+    groups.sort_by(|a, b| b.mean.total_cmp(&a.mean));
+    groups
+}
@@ -0,0 +1,536 @@
+use crate::model::{Credit, Crew, Distribution, Episode, Movie, Person};
+use crate::source::{Candidate, RatingsSource};
+use crate::tvshow::Error;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Number of search results to return, similar to the imdb.com search page
+const MAX_CANDIDATES: usize = 25;
+
+/// A series or film from `title.basics`
+#[derive(Debug, Clone)]
+struct Title {
+    primary_title: String,
+    original_title: String,
+    kind: &'static str,
+    start_year: Option<u32>,
+    end_year: Option<u32>,
+}
+
+impl Title {
+    fn candidate(&self, id: &str) -> Candidate {
+        let years = match (self.start_year, self.end_year) {
+            (Some(start), Some(end)) if start != end => Some(format!("{}–{}", start, end)),
+            (Some(start), Some(_)) => Some(start.to_string()),
+            (Some(start), None) if self.kind == "TV Series" => Some(format!("{}–", start)),
+            (Some(start), None) => Some(start.to_string()),
+            (None, _) => None,
+        };
+        Candidate {
+            id: id.to_string(),
+            title: self.primary_title.clone(),
+            years,
+            kind: Some(self.kind.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EpisodeRow {
+    id: String,
+    season: Option<u32>,
+    episode: Option<u32>,
+    title: String,
+    rating: Option<(f32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+struct PersonRow {
+    name: String,
+    /// e.g. "actor" from "actor,producer,director"
+    profession: Option<String>,
+    birth_year: Option<u32>,
+}
+
+/// Offline ratings backed by the IMDb non-commercial datasets
+///
+/// See <https://developer.imdb.com/non-commercial-datasets/> for the file formats.
+///
+/// Only the series, films and people are loaded up front. Episodes are streamed from the files
+/// when a series is first looked up, as keeping every episode in memory would take gigabytes.
+#[derive(Debug, Default)]
+pub struct Dataset {
+    dir: PathBuf,
+    series: HashMap<String, Title>,
+    movies: HashMap<String, Title>,
+    /// Ratings of the series and films, episode ratings are read with the episodes
+    ratings: HashMap<String, (f32, u32)>,
+    people: HashMap<String, PersonRow>,
+    /// Series and films per person, with the role
+    credits: HashMap<String, Vec<(String, String)>>,
+    /// Episodes of the series looked up so far
+    episodes: Mutex<HashMap<String, Arc<Vec<EpisodeRow>>>>,
+}
+
+impl Dataset {
+    /// Load the series and films from `title.basics.tsv.gz` and `title.ratings.tsv.gz` in `dir`
+    ///
+    /// Episodes are read from `title.episode.tsv.gz` when needed. People are only available
+    /// when `name.basics.tsv.gz` is there as well, with their credits from
+    /// `title.principals.tsv.gz` and episode directors and writers from `title.crew.tsv.gz`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Dataset> {
+        let dir = dir.as_ref();
+        let mut dataset = Dataset {
+            dir: dir.to_path_buf(),
+            ..Default::default()
+        };
+
+        info!("Loading dataset from {}", dir.display());
+
+        for_each_row(&dir.join("title.basics.tsv.gz"), |row| {
+            // tconst, titleType, primaryTitle, originalTitle, isAdult, startYear, endYear, ...
+            if let [id, kind, primary, original, _, start_year, end_year, ..] = row {
+                match *kind {
+                    "tvSeries" | "tvMiniSeries" => {
+                        let kind = match *kind {
+                            "tvSeries" => "TV Series",
+                            _ => "TV Mini Series",
+                        };
+                        dataset.series.insert(
+                            id.to_string(),
+                            Title {
+                                primary_title: primary.to_string(),
+                                original_title: original.to_string(),
+                                kind,
+                                start_year: start_year.parse().ok(),
+                                end_year: end_year.parse().ok(),
+                            },
+                        );
+                    }
+                    "movie" | "tvMovie" => {
+                        let kind = match *kind {
+                            "movie" => "Movie",
+                            _ => "TV Movie",
+                        };
+                        dataset.movies.insert(
+                            id.to_string(),
+                            Title {
+                                primary_title: primary.to_string(),
+                                original_title: original.to_string(),
+                                kind,
+                                start_year: start_year.parse().ok(),
+                                end_year: None,
+                            },
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        })?;
+        info!(
+            "loaded {} series and {} movies",
+            dataset.series.len(),
+            dataset.movies.len()
+        );
+
+        for_each_row(&dir.join("title.ratings.tsv.gz"), |row| {
+            // tconst, averageRating, numVotes
+            if let [id, rating, votes] = row {
+                if !dataset.series.contains_key(*id) && !dataset.movies.contains_key(*id) {
+                    return;
+                }
+                if let (Ok(rating), Ok(votes)) = (rating.parse(), votes.parse()) {
+                    dataset.ratings.insert(id.to_string(), (rating, votes));
+                }
+            }
+        })?;
+        info!("loaded {} ratings", dataset.ratings.len());
+
+        let principals = dir.join("title.principals.tsv.gz");
+        let names = dir.join("name.basics.tsv.gz");
+        if names.exists() {
+            if principals.exists() {
+                dataset.load_credits(&principals)?;
+            }
+            dataset.load_people(&names)?;
+        }
+
+        Ok(dataset)
+    }
+
+    /// Load credits on the known series and films
+    fn load_credits(&mut self, principals: &Path) -> Result<()> {
+        for_each_row(principals, |row| {
+            // tconst, ordering, nconst, category, job, characters
+            if let [id, _, person, category, ..] = row {
+                if self.series.contains_key(*id) || self.movies.contains_key(*id) {
+                    self.credits
+                        .entry(person.to_string())
+                        .or_default()
+                        .push((id.to_string(), category.to_string()));
+                }
+            }
+        })?;
+        info!("loaded credits for {} people", self.credits.len());
+        Ok(())
+    }
+
+    /// Load the people that are credited on a series or film
+    fn load_people(&mut self, names: &Path) -> Result<()> {
+        let mut people = HashMap::new();
+
+        for_each_row(names, |row| {
+            // nconst, primaryName, birthYear, deathYear, primaryProfession, knownForTitles
+            if let [id, name, birth_year, _, profession, ..] = row {
+                let id = id.to_string();
+                if self.credits.contains_key(&id) {
+                    people.insert(
+                        id,
+                        PersonRow {
+                            name: name.to_string(),
+                            profession: profession
+                                .split(',')
+                                .next()
+                                .filter(|p| !p.is_empty() && *p != "\\N")
+                                .map(str::to_string),
+                            birth_year: birth_year.parse().ok(),
+                        },
+                    );
+                }
+            }
+        })?;
+        self.people = people;
+        info!("loaded {} people", self.people.len());
+
+        Ok(())
+    }
+
+    /// Find the series matching `name`, exact title matches first and then by number of votes
+    pub fn find(&self, name: &str) -> Vec<Candidate> {
+        self.find_in(&self.series, name)
+    }
+
+    /// Find the films matching `name`, ordered like [`Dataset::find`]
+    pub fn find_movies(&self, name: &str) -> Vec<Candidate> {
+        self.find_in(&self.movies, name)
+    }
+
+    /// Find the people matching `name`, exact matches first and then by number of credits
+    pub fn find_people(&self, name: &str) -> Vec<Candidate> {
+        let name = name.to_lowercase();
+        let credits = |id: &str| self.credits.get(id).map_or(0, Vec::len);
+
+        let mut matches: Vec<_> = self
+            .people
+            .iter()
+            .filter_map(|(id, person)| {
+                let lower = person.name.to_lowercase();
+                let exact = lower == name;
+                if exact || lower.contains(&name) {
+                    Some((exact, credits(id), id, person))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse((m.0, m.1)));
+
+        matches
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|(_, _, id, person)| Candidate {
+                id: id.to_string(),
+                title: person.name.clone(),
+                years: person.birth_year.map(|year| year.to_string()),
+                kind: person.profession.clone(),
+            })
+            .collect()
+    }
+
+    fn find_in(&self, titles: &HashMap<String, Title>, name: &str) -> Vec<Candidate> {
+        let name = name.to_lowercase();
+        let votes = |id: &str| self.ratings.get(id).map_or(0, |(_, votes)| *votes);
+
+        let mut matches: Vec<_> = titles
+            .iter()
+            .filter_map(|(id, title)| {
+                let primary = title.primary_title.to_lowercase();
+                let exact = primary == name || title.original_title.to_lowercase() == name;
+                if exact || primary.contains(&name) {
+                    Some((exact, votes(id), id, title))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse((m.0, m.1)));
+
+        matches
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|(_, _, id, title)| title.candidate(id))
+            .collect()
+    }
+
+    /// The episodes of a series, streamed from the files the first time
+    async fn episode_rows(&self, id: &str) -> Result<Arc<Vec<EpisodeRow>>> {
+        if !self.series.contains_key(id) {
+            return Err(Error::NotFound(id.to_string()).into());
+        }
+        if let Some(rows) = self.episodes.lock().unwrap().get(id) {
+            return Ok(rows.clone());
+        }
+
+        let dir = self.dir.clone();
+        let series = id.to_string();
+        let rows = tokio::task::spawn_blocking(move || load_episodes(&dir, &series)).await??;
+        if rows.is_empty() {
+            return Err(Error::NotFound(id.to_string()).into());
+        }
+
+        let rows = Arc::new(rows);
+        self.episodes
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), rows.clone());
+        Ok(rows)
+    }
+}
+
+/// Read the episodes of a series, with their titles and ratings
+fn load_episodes(dir: &Path, series: &str) -> Result<Vec<EpisodeRow>> {
+    info!("Loading episodes of {}", series);
+
+    let mut rows = Vec::new();
+    for_each_row(&dir.join("title.episode.tsv.gz"), |row| {
+        // tconst, parentTconst, seasonNumber, episodeNumber (`\N` when missing)
+        if let [id, parent, season, episode] = row {
+            if *parent == series {
+                rows.push(EpisodeRow {
+                    id: id.to_string(),
+                    season: season.parse().ok(),
+                    episode: episode.parse().ok(),
+                    title: String::new(),
+                    rating: None,
+                });
+            }
+        }
+    })?;
+    if rows.is_empty() {
+        return Ok(rows);
+    }
+
+    let index: HashMap<String, usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (row.id.clone(), i))
+        .collect();
+    for_each_row(&dir.join("title.basics.tsv.gz"), |row| {
+        if let [id, "tvEpisode", primary, ..] = row {
+            if let Some(i) = index.get(*id) {
+                rows[*i].title = primary.to_string();
+            }
+        }
+    })?;
+    for_each_row(&dir.join("title.ratings.tsv.gz"), |row| {
+        if let [id, rating, votes] = row {
+            if let (Some(i), Ok(rating), Ok(votes)) =
+                (index.get(*id), rating.parse(), votes.parse())
+            {
+                rows[*i].rating = Some((rating, votes));
+            }
+        }
+    })?;
+    info!("loaded {} episodes of {}", rows.len(), series);
+
+    Ok(rows)
+}
+
+/// Read the directors and writers of some episodes, by name
+fn load_crew(dir: &Path, ids: &[String]) -> Result<HashMap<String, Crew>> {
+    let crew_file = dir.join("title.crew.tsv.gz");
+    let names_file = dir.join("name.basics.tsv.gz");
+    if !crew_file.exists() || !names_file.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let split = |column: &str| -> Vec<String> {
+        column
+            .split(',')
+            .filter(|id| !id.is_empty() && *id != "\\N")
+            .map(str::to_string)
+            .collect()
+    };
+    let wanted: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let mut crew = HashMap::new();
+    for_each_row(&crew_file, |row| {
+        // tconst, directors, writers
+        if let [id, directors, writers] = row {
+            if wanted.contains(*id) {
+                crew.insert(id.to_string(), (split(directors), split(writers)));
+            }
+        }
+    })?;
+
+    let people: HashSet<&str> = crew
+        .values()
+        .flat_map(|(directors, writers): &(Vec<String>, Vec<String>)| {
+            directors.iter().chain(writers).map(String::as_str)
+        })
+        .collect();
+    let mut names = HashMap::new();
+    for_each_row(&names_file, |row| {
+        if let [id, name, ..] = row {
+            if people.contains(*id) {
+                names.insert(id.to_string(), name.to_string());
+            }
+        }
+    })?;
+    info!("loaded crew for {} episodes", crew.len());
+
+    let name = |id: &String| names.get(id).cloned();
+    Ok(crew
+        .into_iter()
+        .map(|(id, (directors, writers))| {
+            let crew = Crew {
+                directors: directors.iter().filter_map(name).collect(),
+                writers: writers.iter().filter_map(name).collect(),
+            };
+            (id, crew)
+        })
+        .collect())
+}
+
+#[async_trait]
+impl RatingsSource for Dataset {
+    async fn search(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(self.find(name))
+    }
+
+    async fn resolve_id(&self, id: &str) -> Result<Candidate> {
+        let series = self.series.get(id).ok_or(Error::NotFound(id.to_string()))?;
+        Ok(series.candidate(id))
+    }
+
+    async fn seasons(&self, id: &str) -> Result<Vec<String>> {
+        let rows = self.episode_rows(id).await?;
+        let mut seasons: Vec<_> = rows.iter().map(|e| e.season).collect();
+        seasons.sort();
+        seasons.dedup();
+
+        Ok(seasons.into_iter().map(season_name).collect())
+    }
+
+    async fn episodes(&self, id: &str, season: &str) -> Result<Vec<Episode>> {
+        let rows = self.episode_rows(id).await?;
+        let mut rows: Vec<_> = rows
+            .iter()
+            .filter(|e| season_name(e.season) == season)
+            .collect();
+        rows.sort_by_key(|e| e.episode.unwrap_or(u32::MAX));
+
+        let episodes = rows
+            .into_iter()
+            .map(|row| Episode {
+                id: Some(row.id.clone()),
+                number: row.episode,
+                title: row.title.clone(),
+                air_date: None,
+                rating: row.rating.map(|(rating, _)| rating),
+                votes: row.rating.map(|(_, votes)| votes),
+                ..Default::default()
+            })
+            .collect();
+        Ok(episodes)
+    }
+
+    async fn search_movies(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(self.find_movies(name))
+    }
+
+    async fn crew(&self, ids: &[String]) -> Result<HashMap<String, Crew>> {
+        let dir = self.dir.clone();
+        let ids = ids.to_vec();
+        tokio::task::spawn_blocking(move || load_crew(&dir, &ids)).await?
+    }
+
+    async fn distribution(&self, _id: &str) -> Result<Distribution> {
+        Err(Error::Unsupported("the IMDb datasets").into())
+    }
+
+    async fn search_people(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(self.find_people(name))
+    }
+
+    async fn person(&self, id: &str) -> Result<Person> {
+        let person = self.people.get(id).ok_or(Error::NotFound(id.to_string()))?;
+        let credits = self.credits.get(id).map_or(&[][..], Vec::as_slice);
+
+        let credits = credits
+            .iter()
+            .filter_map(|(title_id, role)| {
+                let title = self
+                    .series
+                    .get(title_id)
+                    .or_else(|| self.movies.get(title_id))?;
+                let rating = self.ratings.get(title_id);
+                Some(Credit {
+                    id: title_id.clone(),
+                    title: title.primary_title.clone(),
+                    kind: Some(title.kind.to_string()),
+                    year: title.start_year.map(|year| year as i32),
+                    role: role.clone(),
+                    rating: rating.map(|(rating, _)| *rating),
+                    votes: rating.map(|(_, votes)| *votes),
+                })
+            })
+            .collect();
+
+        Ok(Person {
+            id: id.to_string(),
+            name: person.name.clone(),
+            credits,
+        })
+    }
+
+    async fn movie(&self, id: &str) -> Result<Movie> {
+        let movie = self.movies.get(id).ok_or(Error::NotFound(id.to_string()))?;
+        let rating = self.ratings.get(id);
+        Ok(Movie {
+            id: id.to_string(),
+            title: movie.primary_title.clone(),
+            release_date: None,
+            year: movie.start_year.map(|year| year as i32),
+            rating: rating.map(|(rating, _)| *rating),
+            votes: rating.map(|(_, votes)| *votes),
+        })
+    }
+}
+
+/// Name episodes without a season number the same way the imdb.com season tabs do
+fn season_name(season: Option<u32>) -> String {
+    match season {
+        Some(season) => season.to_string(),
+        None => "Unknown".to_string(),
+    }
+}
+
+/// Call `f` with the columns of each row in a gzipped TSV file, skipping the header
+fn for_each_row(path: &Path, mut f: impl FnMut(&[&str])) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let reader = BufReader::new(GzDecoder::new(file));
+
+    for line in reader.lines().skip(1) {
+        let line = line.with_context(|| format!("reading {}", path.display()))?;
+        let columns: Vec<&str> = line.split('\t').collect();
+        f(&columns);
+    }
+    Ok(())
+}
@@ -1,2 +1,10 @@
+pub mod changepoint;
+pub mod client;
+pub mod crew;
+pub mod dataset;
+pub mod model;
+pub mod parse;
 pub mod plot;
+pub mod source;
+pub mod stats;
 pub mod tvshow;
@@ -1,7 +1,15 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use imrs::{plot, tvshow};
-use tracing::info;
+use clap::{Args, Parser, Subcommand};
+use imrs::crew::Role;
+use imrs::dataset::Dataset;
+use imrs::plot::{ChartKind, ColorScale, Format, LegendPosition, Output, Overlays, PlotOptions};
+use imrs::source::{ProgressEvent, RatingsSource};
+use imrs::stats::{Highlight, Stats};
+use imrs::{changepoint, crew, plot, tvshow};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -9,17 +17,219 @@ struct Cli {
     #[arg(short, long, default_value = "info")]
     log_level: String,
 
+    /// Directory with the IMDb TSV datasets, used instead of scraping imdb.com
+    #[arg(short, long)]
+    dataset: Option<PathBuf>,
+
+    #[command(flatten)]
+    plot: PlotArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Size, output and styling of the charts
+#[derive(Args, Debug)]
+struct PlotArgs {
+    /// File to write the chart to
+    #[arg(short, long, global = true, default_value = "chart.png")]
+    output: PathBuf,
+
+    /// Image format, "png", "jpeg", "webp" or "svg", picked from the output file name by default
+    #[arg(long, global = true)]
+    format: Option<Format>,
+
+    #[arg(long, global = true, default_value_t = 1200, value_parser = clap::value_parser!(u32).range(1..=10_000))]
+    width: u32,
+
+    #[arg(long, global = true, default_value_t = 400, value_parser = clap::value_parser!(u32).range(1..=10_000))]
+    height: u32,
+
+    /// Chart title, with {title} replaced by the show, film or person
+    #[arg(long, global = true)]
+    title: Option<String>,
+
+    /// Range of the rating axis, e.g. "--y-range 5 10"
+    #[arg(long, global = true, num_args = 2, value_names = ["MIN", "MAX"])]
+    y_range: Option<Vec<f32>>,
+
+    /// Font family for all text
+    #[arg(long, global = true)]
+    font: Option<String>,
+
+    /// Season legend position: upper-left, upper-right, lower-left, lower-right, right, below
+    /// or none
+    #[arg(long, global = true, default_value = "lower-left")]
+    legend: LegendPosition,
+}
+
+impl PlotArgs {
+    fn options(&self) -> PlotOptions {
+        let defaults = PlotOptions::default();
+        PlotOptions {
+            width: self.width,
+            height: self.height,
+            output: Output::Path(self.output.clone()),
+            format: self
+                .format
+                .or_else(|| Format::from_path(&self.output))
+                .unwrap_or_default(),
+            title: self.title.clone().unwrap_or(defaults.title),
+            y_range: self.y_range.as_ref().map(|range| (range[0], range[1])),
+            font: self.font.clone().unwrap_or(defaults.font),
+            legend: self.legend,
+            ..defaults
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// A test command
     Test {},
 
+    /// Search for TV shows matching a name
+    Search {
+        name: String,
+
+        /// Search for films instead of TV shows
+        #[arg(long, conflicts_with = "people")]
+        movies: bool,
+
+        /// Search for people instead of TV shows
+        #[arg(long)]
+        people: bool,
+    },
+
     /// Look up ratings for a TV show
-    TV { name: String },
+    TV {
+        name: String,
+
+        /// Print the episodes as JSON instead of plotting them
+        #[arg(long)]
+        json: bool,
+
+        /// Leave out specials and episodes without a season number
+        #[arg(long)]
+        regular_only: bool,
+
+        #[command(flatten)]
+        chart: ChartArgs,
+    },
+
+    /// Print statistics of the episode ratings of a TV show
+    Stats {
+        name: String,
+
+        /// Number of best and worst episodes to list
+        #[arg(short, long, default_value_t = 5)]
+        top: usize,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Leave out specials and episodes without a season number
+        #[arg(long)]
+        regular_only: bool,
+    },
+
+    /// Chart how many votes each rating from 1 to 10 got
+    Histogram {
+        /// TV show or film, by name or IMDb id
+        name: String,
+
+        /// Season of the episode to chart instead of the whole show
+        #[arg(long, requires = "episode")]
+        season: Option<String>,
+
+        /// Episode number within the season
+        #[arg(long, requires = "season")]
+        episode: Option<u32>,
+
+        /// Print the vote counts as JSON instead of plotting them
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Chart the ratings of someone's work by year, colored by role
+    Person {
+        /// Name or IMDb id, e.g. "nm0319213"
+        name: String,
+
+        /// Print the credits as JSON instead of plotting them
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare the ratings of the episodes by each director or writer of a TV show
+    Crew {
+        name: String,
+
+        /// Group by "director" or "writer"
+        #[arg(short, long, default_value = "director")]
+        role: Role,
+
+        /// Leave out people credited on fewer episodes
+        #[arg(short, long, default_value_t = 1)]
+        min_episodes: usize,
+
+        /// Print the groups as JSON instead of plotting them
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Chart the ratings of several films in release order, e.g. a franchise
+    Franchise {
+        /// Name used in the chart title
+        #[arg(short, long)]
+        name: String,
+
+        /// Film names or IMDb ids
+        #[arg(required = true)]
+        titles: Vec<String>,
+
+        /// Print the films as JSON instead of plotting them
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// How to chart the episode ratings, and what to draw on top of them
+#[derive(Args, Debug)]
+struct ChartArgs {
+    /// "line", "heatmap" for a grid of seasons by episodes, or "box" or "violin" per season
+    #[arg(long, default_value = "line")]
+    chart: ChartKind,
+
+    /// Heatmap colors, "red-green", "viridis" or "grayscale"
+    #[arg(long, default_value = "red-green")]
+    color_scale: ColorScale,
+
+    /// Detect where the ratings shifted for good and mark it on the chart
+    #[arg(long)]
+    change_points: bool,
+
+    /// Draw a trend line through each season
+    #[arg(long)]
+    season_trends: bool,
+
+    /// Draw a trend line through the whole show
+    #[arg(long)]
+    show_trend: bool,
+}
+
+impl ChartArgs {
+    /// Markers and trend lines are only drawn on line charts
+    fn check(&self) -> Result<()> {
+        let overlays = self.change_points || self.season_trends || self.show_trend;
+        if overlays && self.chart != ChartKind::Line {
+            anyhow::bail!(
+                "--change-points, --season-trends and --show-trend only work with --chart line"
+            );
+        }
+        Ok(())
+    }
 }
 
 #[tokio::main]
@@ -27,29 +237,342 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", format!("{}", cli.log_level));
+        std::env::set_var("RUST_LOG", &cli.log_level);
     };
 
     env_logger::init();
 
+    let options = cli.plot.options();
+
     use Commands::*;
     match &cli.command {
-        Test {} => test(),
-        TV { name } => tv_show(name).await,
+        Test {} => test(&options),
+        Search {
+            name,
+            movies,
+            people,
+        } => search(source(&cli)?.as_ref(), name, *movies, *people).await,
+        TV {
+            name,
+            json,
+            regular_only,
+            chart,
+        } => {
+            let source = source(&cli)?;
+            tv_show(source.as_ref(), name, *json, *regular_only, chart, &options).await
+        }
+        Stats {
+            name,
+            top,
+            json,
+            regular_only,
+        } => stats(source(&cli)?.as_ref(), name, *top, *json, *regular_only).await,
+        Histogram {
+            name,
+            season,
+            episode,
+            json,
+        } => {
+            let episode = season.as_deref().zip(*episode);
+            histogram(source(&cli)?.as_ref(), name, episode, *json, &options).await
+        }
+        Person { name, json } => person(source(&cli)?.as_ref(), name, *json, &options).await,
+        Crew {
+            name,
+            role,
+            min_episodes,
+            json,
+        } => {
+            let source = source(&cli)?;
+            crew(source.as_ref(), name, *role, *min_episodes, *json, &options).await
+        }
+        Franchise { name, titles, json } => {
+            franchise(source(&cli)?.as_ref(), name, titles, *json, &options).await
+        }
     }
 }
 
-fn test() -> Result<()> {
+fn test(options: &PlotOptions) -> Result<()> {
     let results = tvshow::test_ratings();
-    plot::create_plot_svg(&results.name, results.ratings)?;
+    plot::create_plot(&results.name, results.data(), &Overlays::default(), options)?;
+    Ok(())
+}
+
+fn source(cli: &Cli) -> Result<Box<dyn RatingsSource>> {
+    Ok(match &cli.dataset {
+        Some(dir) => Box::new(Dataset::open(dir)?),
+        None => Box::new(tvshow::Scraper::new()),
+    })
+}
+
+async fn search(source: &dyn RatingsSource, name: &str, movies: bool, people: bool) -> Result<()> {
+    let candidates = if movies {
+        source.search_movies(name).await?
+    } else if people {
+        source.search_people(name).await?
+    } else {
+        source.search(name).await?
+    };
+    for candidate in candidates {
+        println!("{}\t{}", candidate.id, candidate);
+    }
     Ok(())
 }
 
-async fn tv_show(name: &str) -> Result<()> {
+async fn tv_show(
+    source: &dyn RatingsSource,
+    name: &str,
+    json: bool,
+    regular_only: bool,
+    chart: &ChartArgs,
+    options: &PlotOptions,
+) -> Result<()> {
+    chart.check()?;
     info!("Looking up ratings for {}", name);
 
-    let results = tvshow::fetch_ratings(name).await?;
-    plot::create_plot(&results.name, results.ratings)?;
+    let bar = ProgressBar::new_spinner();
+    bar.set_message(format!("Searching for {}", name));
+    bar.enable_steady_tick(Duration::from_millis(100));
+    let progress = |event: ProgressEvent| match event {
+        ProgressEvent::Resolved { title, .. } => bar.set_message(title),
+        ProgressEvent::Seasons { count } => {
+            bar.set_style(
+                ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len} seasons").unwrap(),
+            );
+            bar.set_length(count as u64);
+            bar.set_position(0);
+        }
+        ProgressEvent::SeasonDone { .. } => bar.inc(1),
+    };
+
+    let candidate = source.best_match(name).await?;
+    let results = source
+        .fetch_ratings_partial(&candidate.id, &candidate.title, &progress)
+        .await;
+    bar.finish_and_clear();
+
+    let mut results = results?;
+    for failure in &results.failed {
+        warn!("Season {} is missing: {}", failure.season, failure.error);
+    }
+    if regular_only {
+        results = results.regular_seasons();
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    let mut overlays = Overlays {
+        season_trends: chart.season_trends,
+        show_trend: chart.show_trend,
+        ..Default::default()
+    };
+    if chart.change_points {
+        for change in changepoint::detect(&results, &changepoint::Options::default()) {
+            println!(
+                "S{}E{} {}: {:.2} -> {:.2} ({:.0}% confidence)",
+                change.season,
+                change.number.map_or("?".to_string(), |n| n.to_string()),
+                change.title,
+                change.before,
+                change.after,
+                change.confidence * 100.0
+            );
+            overlays.markers.push(change.marker());
+        }
+    }
+    match chart.chart {
+        ChartKind::Line => plot::create_plot(&results.title(), results.data(), &overlays, options)?,
+        ChartKind::Heatmap => plot::create_heatmap(
+            &results.title(),
+            &results.data(),
+            chart.color_scale,
+            options,
+        )?,
+        ChartKind::Box | ChartKind::Violin => plot::create_season_box_plot(
+            &results.title(),
+            &results.data(),
+            chart.chart == ChartKind::Violin,
+            options,
+        )?,
+    }
+
+    Ok(())
+}
+
+async fn stats(
+    source: &dyn RatingsSource,
+    name: &str,
+    top: usize,
+    json: bool,
+    regular_only: bool,
+) -> Result<()> {
+    info!("Looking up statistics for {}", name);
+
+    let candidate = source.best_match(name).await?;
+    let mut results = source
+        .fetch_ratings_partial(&candidate.id, &candidate.title, &())
+        .await?;
+    for failure in &results.failed {
+        warn!("Season {} is missing: {}", failure.season, failure.error);
+    }
+    if regular_only {
+        results = results.regular_seasons();
+    }
+
+    let stats = Stats::new(&results, top);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("{}", results.title());
+    if let Some(overall) = &stats.overall {
+        println!(
+            "{} rated episodes, mean {:.2}, median {:.1}, standard deviation {:.2}",
+            overall.episodes, overall.mean, overall.median, overall.stdev
+        );
+    }
+    if let Some(trend) = stats.trend {
+        println!("Trend {:+.3} per episode", trend);
+    }
+
+    println!("\nseason\tepisodes\tmean\tmedian\tstdev\tmin\tmax\tweighted\tdelta");
+    for season in &stats.seasons {
+        let summary = &season.summary;
+        let delta = stats.deltas.iter().find(|d| d.to == season.season);
+        println!(
+            "{}\t{}\t{:.2}\t{:.1}\t{:.2}\t{:.1}\t{:.1}\t{}\t{}",
+            season.season,
+            summary.episodes,
+            summary.mean,
+            summary.median,
+            summary.stdev,
+            summary.min,
+            summary.max,
+            summary
+                .weighted_mean
+                .map_or("-".to_string(), |m| format!("{:.2}", m)),
+            delta.map_or("-".to_string(), |d| format!("{:+.2}", d.delta)),
+        );
+    }
+
+    let print_episodes = |heading: &str, episodes: &[Highlight]| {
+        println!("\n{}", heading);
+        for e in episodes {
+            let number = e.number.map_or("?".to_string(), |n| n.to_string());
+            println!("{:.1}\tS{}E{}\t{}", e.rating, e.season, number, e.title);
+        }
+    };
+    print_episodes("Best episodes", &stats.best);
+    print_episodes("Worst episodes", &stats.worst);
+
+    Ok(())
+}
+
+async fn histogram(
+    source: &dyn RatingsSource,
+    name: &str,
+    episode: Option<(&str, u32)>,
+    json: bool,
+    options: &PlotOptions,
+) -> Result<()> {
+    info!("Looking up the rating distribution for {}", name);
+
+    let distribution = source.fetch_distribution(name, episode).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&distribution)?);
+        return Ok(());
+    }
+    if let (Some(mean), Some(stdev)) = (distribution.mean(), distribution.stdev()) {
+        println!(
+            "{}: {} votes, mean {:.1}, standard deviation {:.2}",
+            distribution.title,
+            distribution.total(),
+            mean,
+            stdev
+        );
+    }
+    plot::create_histogram_plot(&distribution.title, &distribution.votes, options)?;
+
+    Ok(())
+}
+
+async fn person(
+    source: &dyn RatingsSource,
+    name: &str,
+    json: bool,
+    options: &PlotOptions,
+) -> Result<()> {
+    info!("Looking up credits for {}", name);
+
+    let person = source.fetch_person(name).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&person)?);
+        return Ok(());
+    }
+    plot::create_career_plot(&person.name, &person.data(), options)?;
+
+    Ok(())
+}
+
+async fn crew(
+    source: &dyn RatingsSource,
+    name: &str,
+    role: Role,
+    min_episodes: usize,
+    json: bool,
+    options: &PlotOptions,
+) -> Result<()> {
+    info!("Looking up ratings by {} for {}", role, name);
+
+    let candidate = source.best_match(name).await?;
+    let mut results = source
+        .fetch_ratings_partial(&candidate.id, &candidate.title, &())
+        .await?;
+    source.fetch_crew(&mut results).await?;
+
+    let groups: Vec<_> = crew::group_by(&results, role)
+        .into_iter()
+        .filter(|g| g.ratings.len() >= min_episodes)
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
+        return Ok(());
+    }
+    for group in &groups {
+        println!(
+            "{:.2}\t{:.1}\t{}\t{}",
+            group.mean,
+            group.median,
+            group.ratings.len(),
+            group.name
+        );
+    }
+
+    let groups: Vec<_> = groups.into_iter().map(|g| (g.name, g.ratings)).collect();
+    let title = format!("{} by {}", results.title(), role);
+    plot::create_box_plot(&title, &groups, options)?;
+
+    Ok(())
+}
+
+async fn franchise(
+    source: &dyn RatingsSource,
+    name: &str,
+    titles: &[String],
+    json: bool,
+    options: &PlotOptions,
+) -> Result<()> {
+    info!("Looking up ratings for {} films", titles.len());
+
+    let franchise = source.fetch_franchise(name, titles).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&franchise)?);
+        return Ok(());
+    }
+    plot::create_release_plot(&franchise.name, &franchise.data(), options)?;
 
     Ok(())
 }
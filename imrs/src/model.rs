@@ -0,0 +1,328 @@
+use crate::plot;
+use chrono::NaiveDate;
+use serde::{Serialize, Serializer};
+use std::fmt::{self, Display};
+
+/// A single episode of a TV show
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Episode {
+    /// IMDb id (tconst) of the episode
+    pub id: Option<String>,
+    /// Episode number within the season
+    pub number: Option<u32>,
+    pub title: String,
+    pub air_date: Option<NaiveDate>,
+    /// Weighted average rating, `None` for unrated or unaired episodes
+    pub rating: Option<f32>,
+    pub votes: Option<u32>,
+    /// Names of the credited directors, only filled in by [`crate::source::RatingsSource::fetch_crew`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub directors: Vec<String>,
+    /// Names of the credited writers, only filled in by [`crate::source::RatingsSource::fetch_crew`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writers: Vec<String>,
+}
+
+/// Directors and writers credited on an episode
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Crew {
+    pub directors: Vec<String>,
+    pub writers: Vec<String>,
+}
+
+/// Identifies a season, ordered numerically with specials and unknown episodes last
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SeasonId {
+    Number(u32),
+    /// Season 0, or a season tab named "Specials"
+    Specials,
+    /// Episodes without a season number
+    #[default]
+    Unknown,
+}
+
+impl SeasonId {
+    /// Numbered seasons, as opposed to the specials and unknown buckets
+    pub fn is_regular(&self) -> bool {
+        matches!(self, SeasonId::Number(_))
+    }
+
+    /// Label used in plot legends, e.g. "Season 2"
+    pub fn label(&self) -> String {
+        match self {
+            SeasonId::Number(number) => format!("Season {}", number),
+            SeasonId::Specials => "Specials".to_string(),
+            SeasonId::Unknown => "Unknown season".to_string(),
+        }
+    }
+
+    /// Short label for crowded legends, e.g. "S2"
+    pub fn short_label(&self) -> String {
+        match self {
+            SeasonId::Number(number) => format!("S{}", number),
+            SeasonId::Specials => "Sp".to_string(),
+            SeasonId::Unknown => "?".to_string(),
+        }
+    }
+}
+
+/// Parse a season name as used by IMDb, e.g. "3", "0" or "Unknown"
+impl From<&str> for SeasonId {
+    fn from(name: &str) -> Self {
+        let name = name.trim();
+        match name.parse() {
+            Ok(0) => SeasonId::Specials,
+            Ok(number) => SeasonId::Number(number),
+            Err(_) if name.to_lowercase().starts_with("special") => SeasonId::Specials,
+            Err(_) => SeasonId::Unknown,
+        }
+    }
+}
+
+impl From<u32> for SeasonId {
+    fn from(number: u32) -> Self {
+        match number {
+            0 => SeasonId::Specials,
+            number => SeasonId::Number(number),
+        }
+    }
+}
+
+impl Display for SeasonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeasonId::Number(number) => write!(f, "{}", number),
+            SeasonId::Specials => f.write_str("Specials"),
+            SeasonId::Unknown => f.write_str("Unknown"),
+        }
+    }
+}
+
+/// Serialized as the season name, so `"3"`, `"Specials"` or `"Unknown"`
+impl Serialize for SeasonId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Season {
+    pub season: SeasonId,
+    pub episodes: Vec<Episode>,
+}
+
+/// A season whose episodes could not be fetched
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SeasonFailure {
+    /// Season name as used to fetch it, so it can be retried
+    pub season: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Show {
+    /// IMDb id (tconst) of the show
+    pub id: String,
+    pub name: String,
+    pub seasons: Vec<Season>,
+    /// Seasons missing from `seasons` because fetching them failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<SeasonFailure>,
+}
+
+impl Season {
+    /// Ratings in episode order, leaving out the unrated episodes at the end, e.g. unaired ones
+    pub fn ratings(&self) -> Vec<plot::Point> {
+        let rated = self
+            .episodes
+            .iter()
+            .rposition(|e| e.rating.is_some())
+            .map_or(0, |last| last + 1);
+
+        self.episodes[..rated]
+            .iter()
+            .map(|e| plot::Point {
+                rating: e.rating,
+                votes: e.votes,
+                number: e.number,
+            })
+            .collect()
+    }
+}
+
+impl Show {
+    /// Whether all seasons were fetched
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Name to show in titles, marked when some seasons are missing
+    pub fn title(&self) -> String {
+        if self.is_complete() {
+            self.name.clone()
+        } else {
+            format!("{} (incomplete)", self.name)
+        }
+    }
+
+    pub fn season(&self, season: SeasonId) -> Option<&Season> {
+        self.seasons.iter().find(|s| s.season == season)
+    }
+
+    /// Drop the specials and unknown season buckets, keeping only numbered seasons
+    pub fn regular_seasons(mut self) -> Show {
+        self.seasons.retain(|s| s.season.is_regular());
+        self
+    }
+
+    pub fn episodes(&self) -> impl Iterator<Item = &Episode> {
+        self.seasons.iter().flat_map(|s| s.episodes.iter())
+    }
+
+    /// The ratings in the format used by [`plot`]
+    pub fn data(&self) -> plot::Data {
+        self.seasons
+            .iter()
+            .map(|s| (s.season, s.ratings()))
+            .collect()
+    }
+}
+
+/// A film
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Movie {
+    /// IMDb id (tconst) of the film
+    pub id: String,
+    pub title: String,
+    pub release_date: Option<NaiveDate>,
+    /// Release year, also known when the full release date is not
+    pub year: Option<i32>,
+    pub rating: Option<f32>,
+    pub votes: Option<u32>,
+}
+
+impl Movie {
+    /// Release date, or the start of the release year when only that is known
+    pub fn released(&self) -> Option<NaiveDate> {
+        self.release_date
+            .or_else(|| NaiveDate::from_ymd_opt(self.year?, 1, 1))
+    }
+}
+
+/// Several films charted together, e.g. a franchise or a collection
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Franchise {
+    pub name: String,
+    /// Films in release order, the ones without a known release date last
+    pub movies: Vec<Movie>,
+}
+
+impl Franchise {
+    /// Rated films with a release date, in the format used by [`plot::create_release_plot`]
+    pub fn data(&self) -> Vec<plot::Release> {
+        self.movies
+            .iter()
+            .filter_map(|m| {
+                Some(plot::Release {
+                    date: m.released()?,
+                    title: m.title.clone(),
+                    point: plot::Point {
+                        rating: Some(m.rating?),
+                        votes: m.votes,
+                        number: None,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// A title someone is credited on
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Credit {
+    /// IMDb id (tconst) of the title
+    pub id: String,
+    pub title: String,
+    /// Kind of title, e.g. "Movie" or "TV Series"
+    pub kind: Option<String>,
+    pub year: Option<i32>,
+    /// What they did, e.g. "actor", "director" or "writer"
+    pub role: String,
+    pub rating: Option<f32>,
+    pub votes: Option<u32>,
+}
+
+/// An actor, writer, director etc. with the titles they are credited on
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Person {
+    /// IMDb id (nconst) of the person
+    pub id: String,
+    pub name: String,
+    pub credits: Vec<Credit>,
+}
+
+impl Person {
+    /// Rated credits with a year, per role, in the format used by [`plot::create_career_plot`]
+    pub fn data(&self) -> plot::CareerData {
+        let mut data = plot::CareerData::new();
+        for credit in &self.credits {
+            if let (Some(year), Some(rating)) = (credit.year, credit.rating) {
+                data.entry(credit.role.clone()).or_default().push((
+                    year,
+                    plot::Point {
+                        rating: Some(rating),
+                        votes: credit.votes,
+                        number: None,
+                    },
+                ));
+            }
+        }
+        data
+    }
+}
+
+/// How many votes a title or episode got for each rating from 1 to 10
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    /// IMDb id (tconst) of the title or episode
+    pub id: String,
+    pub title: String,
+    /// Weighted average rating as shown by IMDb
+    pub rating: Option<f32>,
+    /// Number of votes, `votes[0]` for 1 star up to `votes[9]` for 10 stars
+    pub votes: [u32; 10],
+}
+
+impl Distribution {
+    pub fn total(&self) -> u32 {
+        self.votes.iter().sum()
+    }
+
+    /// Unweighted mean of the votes
+    pub fn mean(&self) -> Option<f32> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let sum: u64 = self
+            .votes
+            .iter()
+            .enumerate()
+            .map(|(i, votes)| (i as u64 + 1) * *votes as u64)
+            .sum();
+        Some(sum as f32 / total as f32)
+    }
+
+    /// Standard deviation of the votes, high for polarizing titles
+    pub fn stdev(&self) -> Option<f32> {
+        let mean = self.mean()?;
+        let variance: f32 = self
+            .votes
+            .iter()
+            .enumerate()
+            .map(|(i, votes)| *votes as f32 * (i as f32 + 1.0 - mean).powi(2))
+            .sum::<f32>()
+            / self.total() as f32;
+        Some(variance.sqrt())
+    }
+}
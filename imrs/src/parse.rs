@@ -0,0 +1,833 @@
+//! Parsing of imdb.com pages, kept separate from fetching so it can be tested offline
+
+use crate::model::{Credit, Crew, Distribution, Episode, Movie};
+use crate::source::Candidate;
+use crate::tvshow::Error;
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+pub const TITLE_SELECTOR: &str = "[data-testid=\"hero__pageTitle\"]";
+pub const SEASON_SELECTOR: &str = "[data-testid=\"tab-season-entry\"]";
+pub const EPISODE_SELECTOR: &str = "article.episode-item-wrapper";
+pub const RATING_SELECTOR: &str = "[data-testid=\"ratingGroup--container\"]";
+pub const LINKED_DATA_SELECTOR: &str = "script[type=\"application/ld+json\"]";
+
+/// The JSON payload Next.js embeds in `<script id="__NEXT_DATA__">`
+#[derive(Debug, Deserialize)]
+struct NextData {
+    props: Props,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Props {
+    page_props: PageProps,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageProps {
+    content_data: ContentData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentData {
+    section: EpisodesSection,
+}
+
+#[derive(Debug, Deserialize)]
+struct EpisodesSection {
+    #[serde(default)]
+    seasons: Vec<SeasonTab>,
+    episodes: Option<EpisodeItems>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SeasonTab {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpisodeItems {
+    #[serde(default)]
+    items: Vec<EpisodeItem>,
+    #[serde(default)]
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpisodeItem {
+    id: String,
+    episode: Option<String>,
+    title_text: Option<String>,
+    release_date: Option<ReleaseDate>,
+    aggregate_rating: Option<f32>,
+    vote_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDate {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl From<EpisodeItem> for Episode {
+    fn from(item: EpisodeItem) -> Self {
+        let air_date = item.release_date.and_then(|date| match date {
+            ReleaseDate {
+                year: Some(year),
+                month: Some(month),
+                day: Some(day),
+            } => NaiveDate::from_ymd_opt(year, month, day),
+            _ => None,
+        });
+        Episode {
+            id: Some(item.id),
+            number: item.episode.and_then(|e| e.parse().ok()),
+            title: item.title_text.unwrap_or_default(),
+            air_date,
+            rating: item.aggregate_rating,
+            votes: item.vote_count.filter(|_| item.aggregate_rating.is_some()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Errors reported by a GraphQL query, returned in place of (or next to) `data`
+#[derive(Debug, Deserialize)]
+struct GraphQlErrors {
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Response to [`EPISODES_QUERY`]
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse {
+    data: GraphQlData,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlData {
+    title: GraphQlTitle,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlTitle {
+    episodes: GraphQlEpisodes,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlEpisodes {
+    episodes: Connection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection {
+    edges: Vec<Edge>,
+    page_info: PageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct Edge {
+    node: Node,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Node {
+    id: String,
+    title_text: Option<TitleText>,
+    release_date: Option<ReleaseDate>,
+    ratings_summary: Option<RatingsSummary>,
+    series: Option<NodeSeries>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TitleText {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingsSummary {
+    aggregate_rating: Option<f32>,
+    vote_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeSeries {
+    episode_number: Option<EpisodeNumber>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpisodeNumber {
+    episode_number: Option<u32>,
+}
+
+impl From<Node> for Episode {
+    fn from(node: Node) -> Self {
+        let item = EpisodeItem {
+            id: node.id,
+            episode: node
+                .series
+                .and_then(|s| s.episode_number)
+                .and_then(|e| e.episode_number)
+                .map(|e| e.to_string()),
+            title_text: node.title_text.map(|t| t.text),
+            release_date: node.release_date,
+            aggregate_rating: node
+                .ratings_summary
+                .as_ref()
+                .and_then(|r| r.aggregate_rating),
+            vote_count: node.ratings_summary.and_then(|r| r.vote_count),
+        };
+        item.into()
+    }
+}
+
+/// GraphQL query for the episodes after the ones on the season page
+pub const EPISODES_QUERY: &str = "query TitleEpisodes($const: ID!, $season: String!, $after: ID) {
+  title(id: $const) {
+    episodes {
+      episodes(first: 250, after: $after, filter: { includeSeasons: [$season] }) {
+        edges {
+          node {
+            id
+            titleText { text }
+            releaseDate { day month year }
+            ratingsSummary { aggregateRating voteCount }
+            series { episodeNumber { episodeNumber } }
+          }
+        }
+        pageInfo { hasNextPage endCursor }
+      }
+    }
+  }
+}";
+
+/// One page of a season's episodes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpisodesPage {
+    pub episodes: Vec<Episode>,
+    /// Cursor for fetching the next page with [`EPISODES_QUERY`], if there are more episodes
+    pub next_page: Option<String>,
+}
+
+/// The embedded page data of a ratings page (`/title/tt.../ratings/`)
+#[derive(Debug, Deserialize)]
+struct RatingsNextData {
+    props: RatingsProps,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingsProps {
+    page_props: RatingsPageProps,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingsPageProps {
+    content_data: RatingsContentData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingsContentData {
+    histogram_data: HistogramData,
+    entity_metadata: Option<EntityMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistogramData {
+    histogram_values: Vec<HistogramValue>,
+    aggregate_rating: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistogramValue {
+    rating: usize,
+    vote_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityMetadata {
+    title_text: TitleText,
+}
+
+/// Response to [`CREDITS_QUERY`]
+#[derive(Debug, Deserialize)]
+struct CreditsResponse {
+    data: CreditsData,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditsData {
+    name: Option<GraphQlName>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQlName {
+    name_text: TitleText,
+    credits: CreditConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreditConnection {
+    edges: Vec<CreditEdge>,
+    page_info: PageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditEdge {
+    node: CreditNode,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditNode {
+    category: CreditCategory,
+    title: CreditTitle,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditCategory {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreditTitle {
+    id: String,
+    title_text: Option<TitleText>,
+    title_type: Option<TitleText>,
+    release_year: Option<ReleaseYear>,
+    ratings_summary: Option<RatingsSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseYear {
+    year: Option<i32>,
+}
+
+impl From<CreditNode> for Credit {
+    fn from(node: CreditNode) -> Self {
+        let title = node.title;
+        let rating = title
+            .ratings_summary
+            .as_ref()
+            .and_then(|r| r.aggregate_rating);
+        Credit {
+            id: title.id,
+            title: title.title_text.map(|t| t.text).unwrap_or_default(),
+            kind: title.title_type.map(|t| t.text),
+            year: title.release_year.and_then(|y| y.year),
+            role: node.category.id,
+            rating,
+            votes: title
+                .ratings_summary
+                .and_then(|r| r.vote_count)
+                .filter(|_| rating.is_some()),
+        }
+    }
+}
+
+/// GraphQL query for the titles a person is credited on
+pub const CREDITS_QUERY: &str = "query NameCredits($const: ID!, $after: ID) {
+  name(id: $const) {
+    nameText { text }
+    credits(first: 250, after: $after) {
+      edges {
+        node {
+          category { id }
+          title {
+            id
+            titleText { text }
+            titleType { text }
+            releaseYear { year }
+            ratingsSummary { aggregateRating voteCount }
+          }
+        }
+      }
+      pageInfo { hasNextPage endCursor }
+    }
+  }
+}";
+
+/// One page of a person's credits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CreditsPage {
+    pub name: String,
+    pub credits: Vec<Credit>,
+    /// Cursor for fetching the next page with [`CREDITS_QUERY`], if there are more credits
+    pub next_page: Option<String>,
+}
+
+/// Response to [`CREW_QUERY`]
+#[derive(Debug, Deserialize)]
+struct CrewResponse {
+    data: CrewData,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewData {
+    titles: Vec<Option<CrewTitle>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewTitle {
+    id: String,
+    directors: CrewConnection,
+    writers: CrewConnection,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewConnection {
+    edges: Vec<CrewEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewEdge {
+    node: CrewNode,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewNode {
+    name: CrewName,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CrewName {
+    name_text: TitleText,
+}
+
+impl CrewConnection {
+    fn names(self) -> Vec<String> {
+        self.edges
+            .into_iter()
+            .map(|edge| edge.node.name.name_text.text)
+            .collect()
+    }
+}
+
+/// GraphQL query for the directors and writers of several episodes
+pub const CREW_QUERY: &str = "query TitlesCrew($ids: [ID!]!) {
+  titles(ids: $ids) {
+    id
+    directors: credits(first: 20, filter: { categories: [\"director\"] }) {
+      edges { node { name { nameText { text } } } }
+    }
+    writers: credits(first: 20, filter: { categories: [\"writer\"] }) {
+      edges { node { name { nameText { text } } } }
+    }
+  }
+}";
+
+/// The episodes section of the embedded page data, if the page has it
+fn next_data(document: &Html) -> Option<EpisodesSection> {
+    parse_next_data::<NextData>(document).map(|data| data.props.page_props.content_data.section)
+}
+
+/// The JSON payload Next.js embeds in the page, if there is one that parses as `T`
+fn parse_next_data<T: DeserializeOwned>(document: &Html) -> Option<T> {
+    let script_selector = Selector::parse("script#__NEXT_DATA__").unwrap();
+    let script = document.select(&script_selector).next()?;
+    let json = script.text().collect::<String>();
+
+    match serde_json::from_str(&json) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            warn!(
+                "could not parse __NEXT_DATA__, falling back to selectors: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Parse the results of a title or name search (`/find?q=...`)
+///
+/// For people the candidate's title is their name and the kind their main profession.
+pub fn parse_search_page(html: &str) -> Result<Vec<Candidate>, Error> {
+    let document = Html::parse_document(html);
+
+    let result_selector =
+        Selector::parse(".findResult .result_text, .find-title-result, .find-name-result").unwrap();
+    let title_link_selector = Selector::parse("a.ipc-metadata-list-summary-item__t").unwrap();
+    let link_selector = Selector::parse("a").unwrap();
+    let details_selector = Selector::parse(".ipc-metadata-list-summary-item__tl li").unwrap();
+
+    let id_re = Regex::new(r"/(?:title|name)/((?:tt|nm)\d+)/").unwrap();
+    let year_re = Regex::new(r"^\d{4}").unwrap();
+
+    let mut candidates = Vec::new();
+
+    for result in document.select(&result_selector) {
+        let link = result
+            .select(&title_link_selector)
+            .next()
+            .or_else(|| result.select(&link_selector).next());
+        let Some(link) = link else {
+            continue;
+        };
+        let Some(cap) = link
+            .value()
+            .attr("href")
+            .and_then(|href| id_re.captures(href))
+        else {
+            continue;
+        };
+
+        let mut candidate = Candidate {
+            id: cap[1].to_string(),
+            title: text(link),
+            ..Default::default()
+        };
+
+        // e.g. ["2005–2013", "TV Series"]
+        for detail in result.select(&details_selector) {
+            let detail = text(detail);
+            if year_re.is_match(&detail) {
+                candidate.years = Some(detail);
+            } else if candidate.kind.is_none() {
+                candidate.kind = Some(detail);
+            }
+        }
+
+        info!("candidate: {:?}", candidate);
+        candidates.push(candidate);
+    }
+
+    Ok(candidates)
+}
+
+/// Parse the title from a title's main page (`/title/tt.../`)
+pub fn parse_title_page(html: &str) -> Result<String, Error> {
+    let document = Html::parse_document(html);
+    let title_selector = Selector::parse(TITLE_SELECTOR).unwrap();
+
+    let title = document
+        .select(&title_selector)
+        .next()
+        .ok_or_else(|| Error::layout("", TITLE_SELECTOR))?;
+    Ok(text(title))
+}
+
+/// The schema.org description embedded in title pages
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkedData {
+    name: String,
+    date_published: Option<NaiveDate>,
+    aggregate_rating: Option<AggregateRating>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AggregateRating {
+    rating_value: f32,
+    rating_count: u32,
+}
+
+/// Parse the rating and release date of a film from its title page (`/title/tt.../`)
+///
+/// The id is left empty, as the page is fetched by id anyway.
+pub fn parse_movie_page(html: &str) -> Result<Movie, Error> {
+    let document = Html::parse_document(html);
+    let linked_data_selector = Selector::parse(LINKED_DATA_SELECTOR).unwrap();
+    let title_selector = Selector::parse(TITLE_SELECTOR).unwrap();
+
+    let data: LinkedData = document
+        .select(&linked_data_selector)
+        .find_map(|script| serde_json::from_str(&script.text().collect::<String>()).ok())
+        .ok_or_else(|| Error::layout("", LINKED_DATA_SELECTOR))?;
+
+    // The linked data has HTML entities in the name, e.g. "Ocean&apos;s Eleven"
+    let title = document
+        .select(&title_selector)
+        .next()
+        .map(text)
+        .unwrap_or(data.name);
+
+    Ok(Movie {
+        id: String::new(),
+        title,
+        release_date: data.date_published,
+        year: data.date_published.map(|d| d.year()),
+        rating: data.aggregate_rating.as_ref().map(|r| r.rating_value),
+        votes: data.aggregate_rating.as_ref().map(|r| r.rating_count),
+    })
+}
+
+/// Parse the season tabs from an episode list page (`/title/tt.../episodes/`)
+///
+/// Uses the embedded page data when present and falls back to the markup.
+pub fn parse_seasons_page(html: &str) -> Result<Vec<String>, Error> {
+    let document = Html::parse_document(html);
+
+    if let Some(section) = next_data(&document) {
+        if !section.seasons.is_empty() {
+            return Ok(section.seasons.into_iter().map(|s| s.value).collect());
+        }
+    }
+
+    let season_selector = Selector::parse(SEASON_SELECTOR).unwrap();
+
+    let seasons: Vec<_> = document.select(&season_selector).map(text).collect();
+    if seasons.is_empty() {
+        return Err(Error::layout("", SEASON_SELECTOR));
+    }
+    Ok(seasons)
+}
+
+/// Parse the episodes from an episode list page (`/title/tt.../episodes/?season=...`)
+///
+/// Uses the embedded page data when present and falls back to the markup.
+pub fn parse_episodes_page(html: &str) -> Result<EpisodesPage, Error> {
+    let document = Html::parse_document(html);
+
+    if let Some(EpisodesSection {
+        episodes: Some(episodes),
+        ..
+    }) = next_data(&document)
+    {
+        if !episodes.items.is_empty() {
+            return Ok(EpisodesPage {
+                episodes: episodes.items.into_iter().map(Episode::from).collect(),
+                next_page: episodes.end_cursor.filter(|_| episodes.has_next_page),
+            });
+        }
+    }
+
+    let episode_selector = Selector::parse(EPISODE_SELECTOR).unwrap();
+    let title_selector = Selector::parse(".ipc-title__text").unwrap();
+    let link_selector = Selector::parse("a.ipc-title-link-wrapper").unwrap();
+    let span_selector = Selector::parse("span").unwrap();
+    let rating_selector = Selector::parse(RATING_SELECTOR).unwrap();
+    let vote_count_selector = Selector::parse(".ipc-rating-star--voteCount").unwrap();
+
+    // "S1.E2 ∙ Cat's in the Bag..."
+    let title_re = Regex::new(r"^S\w+\.E(\d+)\s*∙\s*(.*)$").unwrap();
+    let tt_id_re = Regex::new(r"/title/(tt\d+)/").unwrap();
+
+    let mut episodes = Vec::new();
+
+    for article in document.select(&episode_selector) {
+        let mut episode = Episode::default();
+
+        if let Some(title) = article.select(&title_selector).next() {
+            let title = text(title);
+            match title_re.captures(&title) {
+                Some(cap) => {
+                    episode.number = cap[1].parse().ok();
+                    episode.title = cap[2].trim().to_string();
+                }
+                None => episode.title = title,
+            }
+        }
+
+        episode.id = article
+            .select(&link_selector)
+            .next()
+            .and_then(|link| link.value().attr("href"))
+            .and_then(|href| tt_id_re.captures(href))
+            .map(|cap| cap[1].to_string());
+
+        // The air date is in an otherwise unmarked span, e.g. "Sun, Jan 20, 2008"
+        episode.air_date = article
+            .select(&span_selector)
+            .find_map(|span| NaiveDate::parse_from_str(&text(span), "%a, %b %d, %Y").ok());
+
+        // The rating is the first child of the container, unrated and unaired episodes only have
+        // a "Rate" button there, if anything
+        let rating = article
+            .select(&rating_selector)
+            .next()
+            .and_then(|container| container.first_child())
+            .and_then(ElementRef::wrap)
+            .filter(|child| child.value().name() == "span");
+        if let Some(rating) = rating {
+            // <span><svg/>8.7<span>/10</span><span>(32K)</span></span>
+            let ep_rating = rating
+                .children()
+                .find_map(|child| child.value().as_text().filter(|t| !t.trim().is_empty()))
+                .ok_or_else(|| Error::layout("", RATING_SELECTOR))?;
+            let ep_rating: f32 = ep_rating
+                .trim()
+                .parse()
+                .map_err(|_| Error::ParseRating(ep_rating.to_string()))?;
+            episode.rating = Some(ep_rating);
+
+            episode.votes = rating
+                .select(&vote_count_selector)
+                .next()
+                .and_then(|votes| parse_vote_count(&text(votes)));
+        } else {
+            info!("No ratings");
+        }
+
+        episodes.push(episode);
+    }
+
+    if episodes.is_empty() {
+        return Err(Error::layout("", EPISODE_SELECTOR));
+    }
+    Ok(EpisodesPage {
+        episodes,
+        next_page: None,
+    })
+}
+
+/// Fail with the reported messages when a GraphQL response has errors
+fn check_graphql_errors(json: &str) -> Result<(), Error> {
+    let Ok(response) = serde_json::from_str::<GraphQlErrors>(json) else {
+        return Ok(());
+    };
+    if response.errors.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<_> = response.errors.into_iter().map(|e| e.message).collect();
+    Err(Error::GraphQl(messages.join("; ")))
+}
+
+/// Parse the response to [`EPISODES_QUERY`]
+pub fn parse_episodes_query(json: &str) -> Result<EpisodesPage, Error> {
+    check_graphql_errors(json)?;
+    let response: GraphQlResponse =
+        serde_json::from_str(json).map_err(|_| Error::layout("", "data.title.episodes"))?;
+    let connection = response.data.title.episodes.episodes;
+
+    Ok(EpisodesPage {
+        episodes: connection
+            .edges
+            .into_iter()
+            .map(|edge| Episode::from(edge.node))
+            .collect(),
+        next_page: connection
+            .page_info
+            .end_cursor
+            .filter(|_| connection.page_info.has_next_page),
+    })
+}
+
+/// Parse the 1-10 vote histogram from a ratings page (`/title/tt.../ratings/`)
+///
+/// The id is left empty, as the page is fetched by id anyway.
+pub fn parse_ratings_page(html: &str) -> Result<Distribution, Error> {
+    let document = Html::parse_document(html);
+    let content = parse_next_data::<RatingsNextData>(&document)
+        .map(|data| data.props.page_props.content_data)
+        .ok_or_else(|| Error::layout("", "__NEXT_DATA__ histogramData"))?;
+
+    let mut votes = [0; 10];
+    for value in content.histogram_data.histogram_values {
+        if (1..=10).contains(&value.rating) {
+            votes[value.rating - 1] = value.vote_count;
+        }
+    }
+
+    Ok(Distribution {
+        id: String::new(),
+        title: content
+            .entity_metadata
+            .map(|m| m.title_text.text)
+            .unwrap_or_default(),
+        rating: content.histogram_data.aggregate_rating,
+        votes,
+    })
+}
+
+/// Parse a response to [`CREDITS_QUERY`], `None` when there is no such person
+pub fn parse_credits_query(json: &str) -> Result<Option<CreditsPage>, Error> {
+    check_graphql_errors(json)?;
+    let response: CreditsResponse =
+        serde_json::from_str(json).map_err(|_| Error::layout("", "data.name.credits"))?;
+    let Some(name) = response.data.name else {
+        return Ok(None);
+    };
+    let connection = name.credits;
+
+    Ok(Some(CreditsPage {
+        name: name.name_text.text,
+        credits: connection
+            .edges
+            .into_iter()
+            .map(|edge| Credit::from(edge.node))
+            .collect(),
+        next_page: connection
+            .page_info
+            .end_cursor
+            .filter(|_| connection.page_info.has_next_page),
+    }))
+}
+
+/// Parse a response to [`CREW_QUERY`] into the crew per episode id, skipping unknown ids
+pub fn parse_crew_query(json: &str) -> Result<HashMap<String, Crew>, Error> {
+    check_graphql_errors(json)?;
+    let response: CrewResponse =
+        serde_json::from_str(json).map_err(|_| Error::layout("", "data.titles.credits"))?;
+
+    Ok(response
+        .data
+        .titles
+        .into_iter()
+        .flatten()
+        .map(|title| {
+            let crew = Crew {
+                directors: title.directors.names(),
+                writers: title.writers.names(),
+            };
+            (title.id, crew)
+        })
+        .collect())
+}
+
+/// Parse abbreviated vote counts like "(853)", "(1.2K)" or "(2M)"
+fn parse_vote_count(text: &str) -> Option<u32> {
+    let text = text
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim();
+    let (number, multiplier) = match text.chars().last()? {
+        'K' | 'k' => (&text[..text.len() - 1], 1_000.0),
+        'M' | 'm' => (&text[..text.len() - 1], 1_000_000.0),
+        _ => (text, 1.0),
+    };
+    let number: f64 = number.replace(',', "").parse().ok()?;
+    Some((number * multiplier).round() as u32)
+}
@@ -1,65 +1,699 @@
+use crate::model::SeasonId;
+use crate::stats;
 use plotters::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Debug};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use image::ImageEncoder;
+use plotters::coord::types::RangedCoordf32;
 use plotters::coord::Shift;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use serde::{Deserialize, Deserializer};
 use tracing::info;
 
-pub type Data = HashMap<String, Vec<f32>>;
+/// Image format of a rendered chart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Png,
+    Jpeg,
+    /// Lossless WebP
+    WebP,
+    Svg,
+}
 
-pub fn create_plot(title: &str, data: Data) -> Result<()> {
-    let root = BitMapBackend::new("test.png", (1200, 400)).into_drawing_area();
+impl Format {
+    /// Pick the format from a file extension, e.g. "chart.svg"
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()?.to_str()?.parse().ok()
+    }
 
-    create_plot_with_backend(&root, title, data)?;
-    Ok(())
+    /// MIME type, e.g. for the `Content-Type` header
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Png => "image/png",
+            Format::Jpeg => "image/jpeg",
+            Format::WebP => "image/webp",
+            Format::Svg => "image/svg+xml",
+        }
+    }
 }
 
-pub fn create_plot_svg(title: &str, data: Data) -> Result<()> {
-    let root = SVGBackend::new("test.svg", (1200, 400)).into_drawing_area();
-    create_plot_with_backend(&root, title, data)?;
-    Ok(())
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(Format::Png),
+            "jpg" | "jpeg" => Ok(Format::Jpeg),
+            "webp" => Ok(Format::WebP),
+            "svg" => Ok(Format::Svg),
+            _ => Err(anyhow!(
+                "unknown image format {:?}, expected png, jpeg, webp or svg",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = String::deserialize(deserializer)?;
+        format.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Where to write a rendered chart
+#[derive(Clone)]
+pub enum Output {
+    Path(PathBuf),
+    /// Write the encoded image to e.g. stdout or a buffer
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl Output {
+    pub fn writer(writer: impl Write + Send + 'static) -> Output {
+        Output::Writer(Arc::new(Mutex::new(writer)))
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<()> {
+        match self {
+            Output::Path(path) => std::fs::write(path, bytes)?,
+            Output::Writer(writer) => {
+                let mut writer = writer
+                    .lock()
+                    .map_err(|_| anyhow!("output writer poisoned"))?;
+                writer.write_all(bytes)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Output::Writer(_) => f.write_str("Writer"),
+        }
+    }
+}
+
+/// Where to draw the season legend of [`create_plot_with_backend`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LegendPosition {
+    UpperLeft,
+    UpperRight,
+    #[default]
+    LowerLeft,
+    LowerRight,
+    /// Right of the chart, which gets narrower to make room
+    OutsideRight,
+    /// Under the chart, which gets shorter to make room
+    Below,
+    Hidden,
+}
+
+impl FromStr for LegendPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "upper-left" => Ok(LegendPosition::UpperLeft),
+            "upper-right" => Ok(LegendPosition::UpperRight),
+            "lower-left" => Ok(LegendPosition::LowerLeft),
+            "lower-right" => Ok(LegendPosition::LowerRight),
+            "right" | "outside-right" => Ok(LegendPosition::OutsideRight),
+            "below" => Ok(LegendPosition::Below),
+            "none" | "hidden" => Ok(LegendPosition::Hidden),
+            _ => Err(anyhow!(
+                "unknown legend position {:?}, expected upper-left, upper-right, lower-left, \
+                 lower-right, right, below or none",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LegendPosition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let position = String::deserialize(deserializer)?;
+        position.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Size, output and styling of a chart
+#[derive(Debug, Clone)]
+pub struct PlotOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Only used by the `create_*` functions, the `*_with_backend` ones draw on any backend
+    pub output: Output,
+    pub format: Format,
+    /// Chart title, with `{title}` replaced by the show, film or person
+    pub title: String,
+    /// Range of the rating axis, or `None` to pick one for the chart
+    pub y_range: Option<(f32, f32)>,
+    /// Space around the chart, in pixels
+    pub margin: u32,
+    /// Space above the chart, where the title goes
+    pub margin_top: u32,
+    pub font: String,
+    pub title_size: u32,
+    /// Font size of the axes and labels
+    pub label_size: u32,
+    /// Where to put the season legend of the ratings chart
+    pub legend: LegendPosition,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions {
+            width: 1200,
+            height: 400,
+            output: Output::Path("chart.png".into()),
+            format: Format::Png,
+            title: "IMDb Ratings for {title}".to_string(),
+            y_range: None,
+            margin: 30,
+            margin_top: 60,
+            font: "sans-serif".to_string(),
+            title_size: 24,
+            label_size: 12,
+            legend: LegendPosition::LowerLeft,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// Chart area inside the margins, with room for the axis labels
+    fn chart_builder<'a, 'b, DB: DrawingBackend>(
+        &self,
+        root: &'a DrawingArea<DB, Shift>,
+    ) -> ChartBuilder<'a, 'b, DB> {
+        let mut builder = ChartBuilder::on(root);
+        builder
+            .margin(self.margin)
+            .margin_top(self.margin_top)
+            .x_label_area_size(40)
+            .y_label_area_size(40);
+        builder
+    }
+
+    fn y_range(&self, default: Range<f32>) -> Range<f32> {
+        self.y_range.map_or(default, |(min, max)| min..max)
+    }
+
+    fn label_font(&self) -> FontDesc<'_> {
+        (self.font.as_str(), self.label_size).into_font()
+    }
+}
+
+/// Draw with the backend for the format of the options, and encode the result
+macro_rules! render {
+    ($options:expr, |$root:ident| $draw:expr) => {{
+        let options: &PlotOptions = $options;
+        let size = (options.width, options.height);
+        match options.format {
+            Format::Svg => {
+                let mut svg = String::new();
+                {
+                    let $root = SVGBackend::with_string(&mut svg, size).into_drawing_area();
+                    $draw?;
+                    $root.present()?;
+                }
+                Ok(svg.into_bytes())
+            }
+            format => {
+                let len = (options.width as usize)
+                    .checked_mul(options.height as usize)
+                    .and_then(|pixels| pixels.checked_mul(3))
+                    .ok_or_else(|| anyhow!("{}x{} is too large", options.width, options.height))?;
+                let mut buffer = vec![0; len];
+                {
+                    let $root = BitMapBackend::with_buffer(&mut buffer, size).into_drawing_area();
+                    $draw?;
+                    $root.present()?;
+                }
+                encode(&buffer, size, format)
+            }
+        }
+    }};
+}
+
+/// Encode an RGB bitmap
+fn encode(rgb: &[u8], (width, height): (u32, u32), format: Format) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let color = image::ColorType::Rgb8;
+    match format {
+        Format::Png => image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(rgb, width, height, color)?,
+        Format::Jpeg => image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, 90)
+            .write_image(rgb, width, height, color)?,
+        Format::WebP => image::codecs::webp::WebPEncoder::new_lossless(&mut bytes)
+            .write_image(rgb, width, height, color)?,
+        Format::Svg => return Err(anyhow!("SVG is not a bitmap format")),
+    }
+    Ok(bytes)
+}
+
+/// A single episode in the plot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// `None` when the episode is unrated
+    pub rating: Option<f32>,
+    pub votes: Option<u32>,
+    /// Episode number within the season, used to place heatmap cells
+    pub number: Option<u32>,
+}
+
+impl From<f32> for Point {
+    fn from(rating: f32) -> Self {
+        Some(rating).into()
+    }
+}
+
+impl From<Option<f32>> for Point {
+    fn from(rating: Option<f32>) -> Self {
+        Point {
+            rating,
+            votes: None,
+            number: None,
+        }
+    }
+}
+
+/// Where the line chart draws unrated episodes, below all the ratings
+const UNRATED: f32 = -1.0;
+
+/// Ratings per season, iterated in season order
+pub type Data = BTreeMap<SeasonId, Vec<Point>>;
+
+/// Ratings by year, per role, e.g. "actor" or "director"
+pub type CareerData = BTreeMap<String, Vec<(i32, Point)>>;
+
+/// A rated film, placed on the chart by release date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub date: NaiveDate,
+    pub title: String,
+    pub point: Point,
+}
+
+/// A labelled vertical line on the ratings chart, e.g. where the ratings shifted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub season: SeasonId,
+    /// Position of the episode among the plotted ratings of its season, counting from 0
+    pub episode: usize,
+    pub label: String,
+}
+
+impl Marker {
+    /// Position on the x axis of [`create_plot_with_backend`], `None` if the season is not plotted
+    pub fn x(&self, data: &Data) -> Option<usize> {
+        let mut start = 1;
+        for (season, ratings) in data {
+            if *season == self.season {
+                return Some(start + self.episode);
+            }
+            start += ratings.len();
+        }
+        None
+    }
+}
+
+/// Extras drawn on top of the episode ratings by [`create_plot_with_backend`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overlays {
+    pub markers: Vec<Marker>,
+    /// Draw a least squares line through the ratings of each season
+    pub season_trends: bool,
+    /// Draw a least squares line through the ratings of the whole show
+    pub show_trend: bool,
+}
+
+/// Which chart to draw the episode ratings of a show as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartKind {
+    /// Ratings in airing order, one line per season
+    #[default]
+    Line,
+    /// A grid with a row per season and a cell per episode, see [`create_heatmap_with_backend`]
+    Heatmap,
+    /// The spread of the ratings of each season, see [`create_season_box_plot_with_backend`]
+    Box,
+    /// Like [`ChartKind::Box`], shaped by how common each rating is
+    Violin,
+}
+
+impl FromStr for ChartKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "line" => Ok(ChartKind::Line),
+            "heatmap" => Ok(ChartKind::Heatmap),
+            "box" => Ok(ChartKind::Box),
+            "violin" => Ok(ChartKind::Violin),
+            _ => Err(anyhow!(
+                "unknown chart {:?}, expected line, heatmap, box or violin",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ChartKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let chart = String::deserialize(deserializer)?;
+        chart.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Colors of the heatmap cells, from the lowest to the highest rating
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorScale {
+    /// Red through yellow to green
+    #[default]
+    RedGreen,
+    /// Purple through blue to yellow, readable with most kinds of color blindness
+    Viridis,
+    /// Black to white
+    Grayscale,
+}
+
+impl ColorScale {
+    /// Color of `value`, from 0 for the lowest rating to 1 for the highest
+    pub fn color(&self, value: f32) -> RGBColor {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            ColorScale::RedGreen => {
+                let (r, g, b) = HSLColor(value as f64 / 3.0, 0.75, 0.5).rgb();
+                RGBColor(r, g, b)
+            }
+            ColorScale::Viridis => ViridisRGB::get_color(value),
+            ColorScale::Grayscale => BlackWhite::get_color(value),
+        }
+    }
+}
+
+impl FromStr for ColorScale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "red-green" | "redgreen" => Ok(ColorScale::RedGreen),
+            "viridis" => Ok(ColorScale::Viridis),
+            "grayscale" | "greyscale" => Ok(ColorScale::Grayscale),
+            _ => Err(anyhow!(
+                "unknown color scale {:?}, expected red-green, viridis or grayscale",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorScale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scale = String::deserialize(deserializer)?;
+        scale.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Least squares line through the rated points, as its end points at the first and last x
+///
+/// Unrated points are left out of the fit but still count for the end points.
+pub fn trend_line(points: &[(usize, Option<f32>)]) -> Option<[(usize, f32); 2]> {
+    let rated: Vec<_> = points
+        .iter()
+        .filter_map(|(x, rating)| Some((*x as f32, (*rating)?)))
+        .collect();
+    let (slope, intercept) = stats::linear_fit(&rated)?;
+    let first = points.first()?.0;
+    let last = points.last()?.0;
+    let y = |x: usize| slope * x as f32 + intercept;
+    Some([(first, y(first)), (last, y(last))])
+}
+
+/// Shows with at least this many seasons get short legend labels, e.g. "S12"
+const COMPACT_LEGEND: usize = 20;
+
+/// Season legend, laid out before the chart is built so the chart can make room for it
+///
+/// The entries fill columns from the top, and wrap to a new column when they run out of height,
+/// or for [`LegendPosition::Below`], fill rows and wrap when they run out of width.
+struct Legend {
+    position: LegendPosition,
+    entries: Vec<(String, PaletteColor<Palette99>)>,
+    columns: usize,
+    rows: usize,
+    entry_width: u32,
+    row_height: u32,
+}
+
+impl Legend {
+    const PADDING: u32 = 8;
+    const SWATCH: u32 = 20;
+
+    fn new<DB: DrawingBackend>(
+        root: &DrawingArea<DB, Shift>,
+        data: &Data,
+        options: &PlotOptions,
+    ) -> DrawResult<Option<Legend>, DB> {
+        if options.legend == LegendPosition::Hidden || data.is_empty() {
+            return Ok(None);
+        }
+        let compact = data.len() >= COMPACT_LEGEND;
+        let entries: Vec<_> = data
+            .keys()
+            .enumerate()
+            .map(|(idx, season)| {
+                let label = if compact {
+                    season.short_label()
+                } else {
+                    season.label()
+                };
+                (label, Palette99::pick(idx))
+            })
+            .collect();
+
+        let font = options.label_font().into_text_style(root);
+        let mut text_width = 0;
+        for (label, _) in &entries {
+            text_width = text_width.max(root.estimate_text_size(label, &font)?.0);
+        }
+        let entry_width = Self::SWATCH + 2 * Self::PADDING + text_width;
+        let row_height = options.label_size + 6;
+
+        // Room left for the legend, roughly the size of the plotting area
+        let (width, height) = root.dim_in_pixel();
+        let max_width = width.saturating_sub(2 * options.margin + 40);
+        let max_height = height.saturating_sub(options.margin + options.margin_top + 40);
+        let count = entries.len();
+        let (columns, rows) = match options.legend {
+            LegendPosition::Below => {
+                let columns = (max_width / entry_width).clamp(1, count as u32) as usize;
+                (columns, count.div_ceil(columns))
+            }
+            _ => {
+                let rows = (max_height.saturating_sub(2 * Self::PADDING) / row_height)
+                    .clamp(1, count as u32) as usize;
+                (count.div_ceil(rows), rows)
+            }
+        };
+
+        Ok(Some(Legend {
+            position: options.legend,
+            entries,
+            columns,
+            rows,
+            entry_width,
+            row_height,
+        }))
+    }
+
+    fn width(&self) -> u32 {
+        self.columns as u32 * self.entry_width + Self::PADDING
+    }
+
+    fn height(&self) -> u32 {
+        self.rows as u32 * self.row_height + 2 * Self::PADDING
+    }
+
+    /// Make room for legends outside of the chart
+    fn reserve(&self, builder: &mut ChartBuilder<'_, '_, impl DrawingBackend>, margin: u32) {
+        match self.position {
+            LegendPosition::OutsideRight => {
+                builder.margin_right(margin + self.width() + Self::PADDING);
+            }
+            LegendPosition::Below => {
+                builder.margin_bottom(margin + self.height() + Self::PADDING);
+            }
+            _ => {}
+        }
+    }
+
+    /// Draw the legend inside or next to the plotting area, given as its pixel ranges
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: &DrawingArea<DB, Shift>,
+        (x_pixels, y_pixels): (Range<i32>, Range<i32>),
+        options: &PlotOptions,
+    ) -> DrawResult<(), DB> {
+        let (base_x, base_y) = root.get_base_pixel();
+        let (root_width, root_height) = root.dim_in_pixel();
+        let (left, right) = (x_pixels.start - base_x, x_pixels.end - base_x);
+        let (top, bottom) = (y_pixels.start - base_y, y_pixels.end - base_y);
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let inset = 2 * Self::PADDING as i32;
+        let margin = options.margin as i32;
+
+        let (x, y) = match self.position {
+            LegendPosition::UpperLeft => (left + inset, top + inset),
+            LegendPosition::UpperRight => (right - inset - width, top + inset),
+            LegendPosition::LowerLeft => (left + inset, bottom - inset - height),
+            LegendPosition::LowerRight => (right - inset - width, bottom - inset - height),
+            LegendPosition::OutsideRight => (root_width as i32 - margin - width, top),
+            LegendPosition::Below => (left, root_height as i32 - margin - height),
+            LegendPosition::Hidden => return Ok(()),
+        };
+
+        root.draw(&Rectangle::new(
+            [(x, y), (x + width, y + height)],
+            WHITE.mix(0.8).filled(),
+        ))?;
+        root.draw(&Rectangle::new([(x, y), (x + width, y + height)], BLACK))?;
+
+        let font = options
+            .label_font()
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Left, VPos::Center));
+        for (i, (label, color)) in self.entries.iter().enumerate() {
+            let (column, row) = match self.position {
+                LegendPosition::Below => (i % self.columns, i / self.columns),
+                _ => (i / self.rows, i % self.rows),
+            };
+            let x = x + (Self::PADDING + column as u32 * self.entry_width) as i32;
+            let y = y + (Self::PADDING + row as u32 * self.row_height + self.row_height / 2) as i32;
+            let swatch = Self::SWATCH as i32;
+
+            root.draw(&PathElement::new(
+                vec![(x, y), (x + swatch, y)],
+                color.stroke_width(2),
+            ))?;
+            root.draw(&Circle::new((x + swatch / 2, y), 3, color.filled()))?;
+            root.draw(&Text::new(
+                label.as_str(),
+                (x + swatch + Self::PADDING as i32, y),
+                font.clone(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// Dot radius, scaled so the area follows the number of votes
+fn dot_size(votes: Option<u32>, max_votes: u32) -> u32 {
+    match votes {
+        Some(votes) if max_votes > 0 => {
+            let fraction = votes as f64 / max_votes as f64;
+            2 + (4.0 * fraction.sqrt()).round() as u32
+        }
+        _ => 2,
+    }
+}
+
+/// Render the episode ratings of a show to an image in memory, encoded as `options.format`
+pub fn render_plot(
+    title: &str,
+    data: Data,
+    overlays: &Overlays,
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_plot_with_backend(
+        &root, title, data, overlays, options
+    ))
+}
+
+/// Render the episode ratings of a show and write it to `options.output`
+pub fn create_plot(
+    title: &str,
+    data: Data,
+    overlays: &Overlays,
+    options: &PlotOptions,
+) -> Result<()> {
+    options
+        .output
+        .write(&render_plot(title, data, overlays, options)?)
+}
+
+/// Draw the centered title, e.g. "IMDb Ratings for ..."
+fn draw_title<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let title = options.title.replace("{title}", title);
+    let title_x = root.relative_to_width(0.5) as i32;
+    let title_style = (options.font.as_str(), options.title_size).into_text_style(root);
+    let (size_x, _size_y) = root.estimate_text_size(&title, &title_style)?;
+
+    let title_x = title_x - (size_x / 2) as i32;
+    let title_y = 20;
+
+    root.draw_text(&title, &title_style, (title_x, title_y))
 }
 
 pub fn create_plot_with_backend<DB: DrawingBackend>(
     root: &DrawingArea<DB, Shift>,
     title: &str,
     data: Data,
+    overlays: &Overlays,
+    options: &PlotOptions,
 ) -> DrawResult<(), DB> {
     let total = data.iter().fold(0, |acc, v| acc + v.1.len());
     info!("total: {}", total);
 
+    let max_votes = data
+        .values()
+        .flatten()
+        .filter_map(|p| p.votes)
+        .max()
+        .unwrap_or(0);
+
     root.fill(&WHITE)?;
 
     // let root = root.titled(
     //     format!("IMDb Ratings for {}", title).as_str(),
     //     ("sans-serif", 24),
     // )?;
-    {
-        let title = format!("IMDb Ratings for {}", title);
-        let title_x = root.relative_to_width(0.5) as i32;
-        let title_style = ("sans-serif", 24).into_text_style(root);
-        let (size_x, _size_y) = root.estimate_text_size(&title, &title_style)?;
-
-        let title_x = title_x - (size_x / 2) as i32;
-        let title_y = 20;
-
-        root.draw_text(&title, &title_style, (title_x, title_y))?;
-    }
-    let mut chart = ChartBuilder::on(&root)
-        .margin(30)
-        .margin_top(60)
-        .x_label_area_size(40)
-        .y_label_area_size(40)
-        .build_cartesian_2d(
-            (0..total + 1).with_key_points(vec![1, total]),
-            -1.0f32..10.0f32,
-        )?;
+    draw_title(root, title, options)?;
+    let legend = Legend::new(root, &data, options)?;
+    let mut builder = options.chart_builder(root);
+    if let Some(legend) = &legend {
+        legend.reserve(&mut builder, options.margin);
+    }
+    let mut chart = builder.build_cartesian_2d(
+        (0..total + 1).with_key_points(vec![1, total]),
+        options.y_range(UNRATED..10.0),
+    )?;
 
     chart
         .configure_mesh()
         .x_desc("Episode")
         .y_desc("Rating")
-        .light_line_style(&WHITE)
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .light_line_style(WHITE)
         //.x_max_light_lines(400)
         //.x_labels(300)
         .disable_x_mesh()
@@ -67,46 +701,672 @@ pub fn create_plot_with_backend<DB: DrawingBackend>(
 
     let mut start: usize = 1;
 
-    let mut seasons: Vec<_> = data.keys().collect();
-    seasons.sort_by(|a, b| a.cmp(b));
-    for (idx, season) in seasons.iter().enumerate() {
+    for (idx, ratings) in data.values().enumerate() {
         let color = Palette99::pick(idx);
         let dot_color = color.filled();
 
-        let ratings = data.get(*season).unwrap();
         let data: Vec<_> = ratings
             .iter()
             .enumerate()
-            .map(|(i, x)| (start + i, *x))
+            .map(|(i, p)| (start + i, p.rating, dot_size(p.votes, max_votes)))
             .collect();
+        let drawn = data
+            .iter()
+            .map(|(x, rating, size)| (*x, rating.unwrap_or(UNRATED), *size));
 
         info!("season: {:?}", data);
 
         // Lines
-        chart
-            .draw_series(LineSeries::new(
-                data.iter().map(|(x, y)| (*x, *y)),
-                color.stroke_width(2),
-            ))?
-            .label(format!("Season {}", season))
-            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.filled()));
+        chart.draw_series(LineSeries::new(
+            drawn.clone().map(|(x, y, _)| (x, y)),
+            color.stroke_width(2),
+        ))?;
         // Dots
-        chart.draw_series(
-            data.iter()
-                .map(move |(x, y)| Circle::new((*x, *y), 2, dot_color)),
-        )?;
+        chart.draw_series(drawn.map(move |(x, y, size)| Circle::new((x, y), size, dot_color)))?;
+
+        if overlays.season_trends {
+            let points: Vec<_> = data.iter().map(|(x, y, _)| (*x, *y)).collect();
+            if let Some(line) = trend_line(&points) {
+                let color = Palette99::pick(idx).mix(0.6);
+                chart.draw_series(LineSeries::new(line, color.stroke_width(3)))?;
+            }
+        }
 
         start += ratings.len();
     }
 
-    // chart
-    //     .configure_series_labels()
-    //     .position(SeriesLabelPosition::LowerLeft)
-    //     .background_style(&WHITE.mix(0.8))
-    //     .border_style(&BLACK)
-    //     .draw()?;
+    if overlays.show_trend {
+        let points: Vec<_> = data
+            .values()
+            .flatten()
+            .enumerate()
+            .map(|(i, p)| (i + 1, p.rating))
+            .collect();
+        if let Some(line) = trend_line(&points) {
+            chart.draw_series(LineSeries::new(line, BLACK.mix(0.7).stroke_width(2)))?;
+        }
+    }
+
+    let y_range = options.y_range(UNRATED..10.0);
+    for marker in &overlays.markers {
+        let Some(x) = marker.x(&data) else {
+            continue;
+        };
+        chart.draw_series(std::iter::once(PathElement::new(
+            vec![(x, y_range.start), (x, y_range.end)],
+            BLACK.mix(0.6).stroke_width(2),
+        )))?;
+        chart.draw_series(std::iter::once(Text::new(
+            marker.label.clone(),
+            (x, y_range.end - 0.2),
+            options.label_font().color(&BLACK),
+        )))?;
+    }
+
+    if let Some(legend) = legend {
+        legend.draw(root, chart.plotting_area().get_pixel_range(), options)?;
+    }
 
     //root.present()?;
 
     Ok(())
 }
+
+/// Render a season-by-episode heatmap of a show to an image in memory, encoded as `options.format`
+pub fn render_heatmap(
+    title: &str,
+    data: &Data,
+    scale: ColorScale,
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_heatmap_with_backend(
+        &root, title, data, scale, options
+    ))
+}
+
+/// Render a season-by-episode heatmap of a show and write it to `options.output`
+pub fn create_heatmap(
+    title: &str,
+    data: &Data,
+    scale: ColorScale,
+    options: &PlotOptions,
+) -> Result<()> {
+    options
+        .output
+        .write(&render_heatmap(title, data, scale, options)?)
+}
+
+/// Plot a grid with a row per season and a column per episode, colored and labelled by rating
+///
+/// The colors span the range of the rating axis, or the ratings of the show when not set.
+/// Unrated episodes are drawn as gray cells marked "-", and seasons shorter than the longest
+/// one leave the rest of their row empty. Episodes sharing a number get a cell each.
+pub fn create_heatmap_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    data: &Data,
+    scale: ColorScale,
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let seasons: Vec<_> = data.keys().collect();
+    let rows = seasons.len().max(1);
+
+    // Cells go by episode number, leaving gaps for missing episodes. Episodes without a
+    // number, or with the number of an earlier cell, go in the next free cell
+    let first = data
+        .values()
+        .flatten()
+        .filter_map(|p| p.number)
+        .min()
+        .unwrap_or(1);
+    let cells: Vec<Vec<_>> = data
+        .values()
+        .map(|ratings| {
+            let mut next = 0;
+            let mut taken = HashSet::new();
+            ratings
+                .iter()
+                .map(|point| {
+                    let mut column = point.number.map_or(next, |n| (n - first) as usize);
+                    while !taken.insert(column) {
+                        column += 1;
+                    }
+                    next = column + 1;
+                    (column, point)
+                })
+                .collect()
+        })
+        .collect();
+    let columns = cells
+        .iter()
+        .flatten()
+        .map(|(column, _)| column + 1)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let rated = || data.values().flatten().filter_map(|p| p.rating);
+    let (min, max) = options.y_range.unwrap_or_else(|| {
+        (
+            rated().fold(f32::INFINITY, f32::min),
+            rated().fold(f32::NEG_INFINITY, f32::max),
+        )
+    });
+    let normalize = |rating: f32| match max - min {
+        range if range > 0.0 => (rating - min) / range,
+        _ => 1.0,
+    };
+
+    root.fill(&WHITE)?;
+    draw_title(root, title, options)?;
+
+    // Coordinates count half cells, so the centers of the cells are whole numbers to label
+    let step = if columns > 30 { 5 } else { 1 };
+    let episodes = (0..columns)
+        .filter(|i| *i == 0 || (i + first as usize).is_multiple_of(step))
+        .map(|i| 2 * i + 1)
+        .collect();
+    let centers = (0..rows).map(|i| 2 * i + 1).collect();
+    // The first season is the top row
+    let row = |y: usize| rows - 1 - (y / 2).min(rows - 1);
+
+    let mut chart = options.chart_builder(root).build_cartesian_2d(
+        (0..2 * columns).with_key_points(episodes),
+        (0..2 * rows).with_key_points(centers),
+    )?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Episode")
+        .y_desc("Season")
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .x_label_formatter(&|x| format!("{}", x / 2 + first as usize))
+        .y_label_formatter(&|y| {
+            seasons
+                .get(row(*y))
+                .map(|season| season.to_string())
+                .unwrap_or_default()
+        })
+        .disable_mesh()
+        .draw()?;
+
+    let (width, height) = chart.plotting_area().dim_in_pixel();
+    let show_labels = width / columns as u32 >= 3 * options.label_size
+        && height / rows as u32 > options.label_size;
+    let centered = Pos::new(HPos::Center, VPos::Center);
+
+    for (season, ratings) in cells.iter().enumerate() {
+        let y = 2 * (rows - 1 - season);
+        for (column, point) in ratings {
+            let x = 2 * column;
+            let (fill, label) = match point.rating {
+                Some(rating) => (scale.color(normalize(rating)), format!("{:.1}", rating)),
+                None => (RGBColor(220, 220, 220), "-".to_string()),
+            };
+            let cell = [(x, y), (x + 2, y + 2)];
+            chart.draw_series([
+                Rectangle::new(cell, fill.filled()),
+                Rectangle::new(cell, WHITE.stroke_width(1)),
+            ])?;
+            if show_labels {
+                let style = options.label_font().color(&text_color(fill)).pos(centered);
+                chart.draw_series(std::iter::once(Text::new(label, (x + 1, y + 1), style)))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Black or white, whichever is easier to read on `background`
+fn text_color(background: RGBColor) -> RGBColor {
+    let RGBColor(r, g, b) = background;
+    let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luma > 140.0 {
+        BLACK
+    } else {
+        WHITE
+    }
+}
+
+/// Render films by release date to an image in memory, encoded as `options.format`
+pub fn render_release_plot(
+    title: &str,
+    releases: &[Release],
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_release_plot_with_backend(
+        &root, title, releases, options
+    ))
+}
+
+/// Render films by release date and write it to `options.output`
+pub fn create_release_plot(title: &str, releases: &[Release], options: &PlotOptions) -> Result<()> {
+    options
+        .output
+        .write(&render_release_plot(title, releases, options)?)
+}
+
+/// Plot films in release order, with the release date on the x-axis
+pub fn create_release_plot_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    releases: &[Release],
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let mut releases = releases.to_vec();
+    releases.sort_by_key(|r| r.date);
+
+    let max_votes = releases
+        .iter()
+        .filter_map(|r| r.point.votes)
+        .max()
+        .unwrap_or(0);
+
+    // leave room for the dots and labels at both ends
+    let today = chrono::Utc::now().date_naive();
+    let first = releases.first().map_or(today, |r| r.date) - Duration::days(180);
+    let last = releases.last().map_or(today, |r| r.date) + Duration::days(180);
+
+    root.fill(&WHITE)?;
+    draw_title(root, title, options)?;
+
+    let mut chart = options
+        .chart_builder(root)
+        .build_cartesian_2d(first..last, options.y_range(0.0..10.0))?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Release date")
+        .x_label_formatter(&|date| date.format("%Y").to_string())
+        .y_desc("Rating")
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .light_line_style(WHITE)
+        .disable_x_mesh()
+        .draw()?;
+
+    let rated: Vec<_> = releases
+        .iter()
+        .filter_map(|r| Some((r, r.point.rating?)))
+        .collect();
+    let color = Palette99::pick(0);
+    chart.draw_series(LineSeries::new(
+        rated.iter().map(|(r, rating)| (r.date, *rating)),
+        color.stroke_width(2),
+    ))?;
+    chart.draw_series(rated.iter().map(|(r, rating)| {
+        Circle::new(
+            (r.date, *rating),
+            dot_size(r.point.votes, max_votes),
+            color.filled(),
+        )
+    }))?;
+
+    let label_style = options.label_font().color(&BLACK);
+    chart.draw_series(rated.iter().map(|(r, rating)| {
+        Text::new(r.title.clone(), (r.date, rating + 0.6), label_style.clone())
+    }))?;
+
+    Ok(())
+}
+
+/// Render someone's credits by year to an image in memory, encoded as `options.format`
+pub fn render_career_plot(
+    title: &str,
+    data: &CareerData,
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_career_plot_with_backend(
+        &root, title, data, options
+    ))
+}
+
+/// Render someone's credits by year and write it to `options.output`
+pub fn create_career_plot(title: &str, data: &CareerData, options: &PlotOptions) -> Result<()> {
+    options
+        .output
+        .write(&render_career_plot(title, data, options)?)
+}
+
+/// Plot the ratings of someone's credits by year, colored by role
+pub fn create_career_plot_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    data: &CareerData,
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let years = data.values().flatten().map(|(year, _)| *year);
+    let first = years.clone().min().unwrap_or(2000) - 1;
+    let last = years.max().unwrap_or(2000) + 1;
+
+    let max_votes = data
+        .values()
+        .flatten()
+        .filter_map(|(_, p)| p.votes)
+        .max()
+        .unwrap_or(0);
+
+    root.fill(&WHITE)?;
+    draw_title(root, title, options)?;
+
+    let mut chart = options
+        .chart_builder(root)
+        .build_cartesian_2d(first..last, options.y_range(0.0..10.0))?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Year")
+        .y_desc("Rating")
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .light_line_style(WHITE)
+        .disable_x_mesh()
+        .draw()?;
+
+    for (idx, (role, points)) in data.iter().enumerate() {
+        let color = Palette99::pick(idx);
+        chart
+            .draw_series(points.iter().filter_map(|(year, p)| {
+                Some(Circle::new(
+                    (*year, p.rating?),
+                    dot_size(p.votes, max_votes),
+                    color.mix(0.7).filled(),
+                ))
+            }))?
+            .label(role.as_str())
+            .legend(move |(x, y)| Circle::new((x + 10, y), 4, color.filled()));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerLeft)
+        .label_font(options.label_font())
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+/// Render a vote histogram to an image in memory, encoded as `options.format`
+pub fn render_histogram_plot(
+    title: &str,
+    votes: &[u32; 10],
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_histogram_plot_with_backend(
+        &root, title, votes, options
+    ))
+}
+
+/// Render a vote histogram and write it to `options.output`
+pub fn create_histogram_plot(title: &str, votes: &[u32; 10], options: &PlotOptions) -> Result<()> {
+    options
+        .output
+        .write(&render_histogram_plot(title, votes, options)?)
+}
+
+/// Plot the share of votes for each rating from 1 to 10, ignoring the rating axis range
+pub fn create_histogram_plot_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    votes: &[u32; 10],
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let total: u32 = votes.iter().sum();
+    let percent = |votes: u32| match total {
+        0 => 0.0,
+        total => 100.0 * votes as f32 / total as f32,
+    };
+    let max = votes.iter().map(|v| percent(*v)).fold(0.0, f32::max);
+
+    root.fill(&WHITE)?;
+    draw_title(root, title, options)?;
+
+    let mut chart = options
+        .chart_builder(root)
+        .build_cartesian_2d((1u32..10u32).into_segmented(), 0.0f32..(max * 1.1).max(1.0))?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Rating")
+        .y_desc("Votes (%)")
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .light_line_style(WHITE)
+        .disable_x_mesh()
+        .draw()?;
+
+    chart.draw_series(
+        Histogram::vertical(&chart)
+            .style(Palette99::pick(0).filled())
+            .margin(10)
+            .data(
+                votes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, votes)| (i as u32 + 1, percent(*votes))),
+            ),
+    )?;
+
+    Ok(())
+}
+
+/// Render one box per group of ratings to an image in memory, encoded as `options.format`
+pub fn render_box_plot(
+    title: &str,
+    groups: &[(String, Vec<f32>)],
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_box_plot_with_backend(
+        &root, title, groups, options
+    ))
+}
+
+/// Render one box per group of ratings and write it to `options.output`
+pub fn create_box_plot(
+    title: &str,
+    groups: &[(String, Vec<f32>)],
+    options: &PlotOptions,
+) -> Result<()> {
+    options
+        .output
+        .write(&render_box_plot(title, groups, options)?)
+}
+
+/// Plot one box per group of ratings, e.g. the episodes by each director, in the given order
+pub fn create_box_plot_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    groups: &[(String, Vec<f32>)],
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    draw_groups(root, title, groups, None, false, options)
+}
+
+/// Render one box or violin per season to an image in memory, encoded as `options.format`
+pub fn render_season_box_plot(
+    title: &str,
+    data: &Data,
+    violin: bool,
+    options: &PlotOptions,
+) -> Result<Vec<u8>> {
+    render!(options, |root| create_season_box_plot_with_backend(
+        &root, title, data, violin, options
+    ))
+}
+
+/// Render one box or violin per season and write it to `options.output`
+pub fn create_season_box_plot(
+    title: &str,
+    data: &Data,
+    violin: bool,
+    options: &PlotOptions,
+) -> Result<()> {
+    options
+        .output
+        .write(&render_season_box_plot(title, data, violin, options)?)
+}
+
+/// Plot the spread of the episode ratings of each season, as a box or as a violin
+///
+/// Unrated episodes are left out, and so are seasons without any rated episodes.
+pub fn create_season_box_plot_with_backend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    data: &Data,
+    violin: bool,
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let groups: Vec<_> = data
+        .iter()
+        .map(|(season, points)| {
+            let ratings: Vec<_> = points.iter().filter_map(|p| p.rating).collect();
+            (season.to_string(), ratings)
+        })
+        .filter(|(_, ratings)| !ratings.is_empty())
+        .collect();
+    draw_groups(root, title, &groups, Some("Season"), violin, options)
+}
+
+/// Draw a box, or a violin, per group next to each other
+fn draw_groups<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    groups: &[(String, Vec<f32>)],
+    x_desc: Option<&str>,
+    violin: bool,
+    options: &PlotOptions,
+) -> DrawResult<(), DB> {
+    let ratings = || {
+        groups
+            .iter()
+            .flat_map(|(_, ratings)| ratings.iter().copied())
+    };
+    let min = ratings().fold(f32::INFINITY, f32::min);
+    let max = ratings().fold(f32::NEG_INFINITY, f32::max);
+    let (min, max) = if min <= max {
+        ((min - 0.5).max(0.0), (max + 0.5).min(10.0))
+    } else {
+        (0.0, 10.0)
+    };
+    let y_range = options.y_range(min..max);
+
+    root.fill(&WHITE)?;
+    draw_title(root, title, options)?;
+
+    let mut chart = options.chart_builder(root).build_cartesian_2d(
+        (0..groups.len().saturating_sub(1) as u32).into_segmented(),
+        y_range.clone(),
+    )?;
+
+    let mut mesh = chart.configure_mesh();
+    if let Some(x_desc) = x_desc {
+        mesh.x_desc(x_desc);
+    }
+    mesh.y_desc("Rating")
+        .label_style(options.label_font())
+        .axis_desc_style(options.label_font())
+        .light_line_style(WHITE)
+        .disable_x_mesh()
+        .x_labels(groups.len())
+        .x_label_formatter(&|value| match value {
+            SegmentValue::CenterOf(idx) => groups
+                .get(*idx as usize)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+            _ => String::new(),
+        })
+        .draw()?;
+
+    // Group `i` is the segment from `i` to `i + 1`, this draws the violins in between
+    let area = chart.plotting_area().strip_coord_spec();
+    let (x_pixels, y_pixels) = area.get_pixel_range();
+    // Ratings go up, pixels go down
+    let area = area.apply_coord_spec(Cartesian2d::<RangedCoordf32, RangedCoordf32>::new(
+        0.0..groups.len() as f32,
+        y_range,
+        (x_pixels, y_pixels.end..y_pixels.start),
+    ));
+
+    for (idx, (_, ratings)) in groups.iter().enumerate() {
+        if ratings.is_empty() {
+            continue;
+        }
+        let color = Palette99::pick(idx);
+        let x = SegmentValue::CenterOf(idx as u32);
+        let quartiles = Quartiles::new(ratings);
+
+        if violin {
+            let center = idx as f32 + 0.5;
+            let outline = violin_outline(ratings, center, 0.4);
+            if !outline.is_empty() {
+                area.draw(&Polygon::new(outline.clone(), color.mix(0.3).filled()))?;
+                area.draw(&PathElement::new(outline, color.stroke_width(2)))?;
+            }
+            let [_, q1, median, q3, _] = quartiles.values();
+            area.draw(&PathElement::new(
+                vec![(center, q1), (center, q3)],
+                color.stroke_width(5),
+            ))?;
+            area.draw(&Circle::new((center, median), 3, WHITE.filled()))?;
+            continue;
+        }
+
+        chart.draw_series(std::iter::once(
+            Boxplot::new_vertical(x.clone(), &quartiles)
+                .width(30)
+                .style(color.stroke_width(2)),
+        ))?;
+        // Show the single episodes as well, as many groups only have a few
+        chart.draw_series(
+            ratings
+                .iter()
+                .map(|rating| Circle::new((x.clone(), *rating), 3, color.mix(0.5).filled())),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Closed outline of a violin around `center`, as wide as `half_width` where the ratings
+/// are densest, empty when there is no spread to draw
+fn violin_outline(ratings: &[f32], center: f32, half_width: f32) -> Vec<(f32, f32)> {
+    let min = ratings.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = ratings.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if ratings.len() < 2 || max <= min {
+        return Vec::new();
+    }
+
+    // Silverman's rule of thumb for the width of the kernels
+    let stdev = stats::stdev(ratings).unwrap_or(0.0);
+    let bandwidth = (1.06 * stdev * (ratings.len() as f32).powf(-0.2)).max(0.05);
+    let density = |y: f32| -> f32 {
+        ratings
+            .iter()
+            .map(|r| (-0.5 * ((y - r) / bandwidth).powi(2)).exp())
+            .sum()
+    };
+
+    const STEPS: usize = 50;
+    let curve: Vec<_> = (0..=STEPS)
+        .map(|i| {
+            let y = min + (max - min) * i as f32 / STEPS as f32;
+            (y, density(y))
+        })
+        .collect();
+    let peak = curve.iter().map(|(_, d)| *d).fold(0.0, f32::max);
+
+    let right = curve
+        .iter()
+        .map(|(y, d)| (center + half_width * d / peak, *y));
+    let left = curve
+        .iter()
+        .rev()
+        .map(|(y, d)| (center - half_width * d / peak, *y));
+    let mut outline: Vec<_> = right.chain(left).collect();
+    outline.push(outline[0]);
+    outline
+}
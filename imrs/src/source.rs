@@ -0,0 +1,377 @@
+use crate::model::{
+    Crew, Distribution, Episode, Franchise, Movie, Person, Season, SeasonFailure, SeasonId,
+};
+use crate::tvshow::{Error, Ratings};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use tracing::{info, warn};
+
+/// A title found when searching
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Candidate {
+    pub id: String,
+    pub title: String,
+    /// Years the title ran, e.g. "2005–2013"
+    pub years: Option<String>,
+    /// Kind of title, e.g. "TV Series" or "TV Mini Series"
+    pub kind: Option<String>,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+        let details: Vec<_> = [&self.years, &self.kind].into_iter().flatten().collect();
+        if !details.is_empty() {
+            let details: Vec<_> = details.iter().map(|d| d.as_str()).collect();
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `text` looks like an IMDb title id, e.g. "tt0386676"
+pub fn is_title_id(text: &str) -> bool {
+    text.len() > 2 && text.starts_with("tt") && text[2..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Whether `text` looks like an IMDb name id, e.g. "nm0000138"
+pub fn is_name_id(text: &str) -> bool {
+    text.len() > 2 && text.starts_with("nm") && text[2..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Whether the best match shares its title with another candidate, e.g. "The Office"
+pub fn is_ambiguous(candidates: &[Candidate]) -> bool {
+    match candidates.split_first() {
+        Some((best, rest)) => rest
+            .iter()
+            .any(|c| c.title.to_lowercase() == best.title.to_lowercase()),
+        None => false,
+    }
+}
+
+/// Something that happened while fetching a show
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// The show to fetch is known
+    Resolved { id: String, title: String },
+    /// Number of seasons that will be fetched
+    Seasons { count: usize },
+    /// A season was fetched, or failed when `ok` is false
+    SeasonDone { season: String, ok: bool },
+}
+
+/// Receives [`ProgressEvent`]s while fetching, `&()` ignores them
+pub trait Progress: Send + Sync {
+    fn event(&self, event: ProgressEvent);
+}
+
+impl Progress for () {
+    fn event(&self, _event: ProgressEvent) {}
+}
+
+impl<F: Fn(ProgressEvent) + Send + Sync> Progress for F {
+    fn event(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// Somewhere to get ratings from, e.g. scraping imdb.com or the offline datasets
+#[async_trait]
+pub trait RatingsSource: Send + Sync + Debug {
+    /// Find titles matching `name`, best match first
+    async fn search(&self, name: &str) -> Result<Vec<Candidate>>;
+
+    /// Look up the title for an IMDb id
+    async fn resolve_id(&self, id: &str) -> Result<Candidate>;
+
+    /// List the seasons of a TV show, as the names used to fetch their episodes
+    async fn seasons(&self, id: &str) -> Result<Vec<String>>;
+
+    /// Fetch the episodes of a single season, in episode order
+    async fn episodes(&self, id: &str, season: &str) -> Result<Vec<Episode>>;
+
+    // The rest is only available from some sources, the others leave the default bodies
+
+    /// Find films matching `name`, best match first
+    async fn search_movies(&self, _name: &str) -> Result<Vec<Candidate>> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Look up the rating and release date of a film
+    async fn movie(&self, _id: &str) -> Result<Movie> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Find people matching `name`, best match first
+    async fn search_people(&self, _name: &str) -> Result<Vec<Candidate>> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Look up the titles a person is credited on, with their ratings
+    async fn person(&self, _id: &str) -> Result<Person> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Number of votes for each rating of a title or a single episode
+    async fn distribution(&self, _id: &str) -> Result<Distribution> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Directors and writers of several episodes by IMDb id, leaving out unknown ids
+    async fn crew(&self, _ids: &[String]) -> Result<HashMap<String, Crew>> {
+        Err(Error::Unsupported("this source").into())
+    }
+
+    /// Fill in the directors and writers of every episode of a show
+    async fn fetch_crew(&self, ratings: &mut Ratings) -> Result<()> {
+        let ids: Vec<_> = ratings.episodes().filter_map(|e| e.id.clone()).collect();
+        let mut crew = self.crew(&ids).await?;
+        info!("found crew for {} of {} episodes", crew.len(), ids.len());
+
+        for season in &mut ratings.seasons {
+            for episode in &mut season.episodes {
+                if let Some(Crew { directors, writers }) =
+                    episode.id.as_ref().and_then(|id| crew.remove(id))
+                {
+                    episode.directors = directors;
+                    episode.writers = writers;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Vote histogram of a show by name or IMDb id, or of one of its episodes
+    async fn fetch_distribution(
+        &self,
+        name: &str,
+        episode: Option<(&str, u32)>,
+    ) -> Result<Distribution> {
+        let show = if is_title_id(name) {
+            self.resolve_id(name).await?
+        } else {
+            self.best_match(name).await?
+        };
+
+        let (id, title) = match episode {
+            None => (show.id, show.title),
+            Some((season, number)) => {
+                let title = format!("{} S{}E{}", show.title, season, number);
+                let episode = self
+                    .episodes(&show.id, season)
+                    .await?
+                    .into_iter()
+                    .find(|e| e.number == Some(number));
+                match episode {
+                    Some(Episode {
+                        id: Some(id),
+                        title: name,
+                        ..
+                    }) => (id, format!("{}: {}", title, name)),
+                    _ => return Err(Error::NotFound(title).into()),
+                }
+            }
+        };
+
+        let distribution = self.distribution(&id).await?;
+        Ok(Distribution {
+            title,
+            ..distribution
+        })
+    }
+
+    /// Look up a person by name or IMDb id
+    async fn fetch_person(&self, name: &str) -> Result<Person> {
+        if is_name_id(name) {
+            return self.person(name).await;
+        }
+        let candidates = self.search_people(name).await?;
+        let best = candidates
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound(name.to_string()))?;
+        self.person(&best.id).await
+    }
+
+    /// Look up several films by name or IMDb id and put them in release order
+    async fn fetch_franchise(&self, name: &str, titles: &[String]) -> Result<Franchise> {
+        let mut movies = try_join_all(titles.iter().map(|title| async move {
+            let id = if is_title_id(title) {
+                title.clone()
+            } else {
+                let candidates = self.search_movies(title).await?;
+                let best = candidates
+                    .into_iter()
+                    .next()
+                    .ok_or(Error::NotFound(title.clone()))?;
+                best.id
+            };
+            self.movie(&id).await
+        }))
+        .await?;
+        movies.sort_by_key(|m| (m.released().is_none(), m.released()));
+
+        Ok(Franchise {
+            name: name.to_string(),
+            movies,
+        })
+    }
+
+    /// Find the best matching title for `name`
+    async fn best_match(&self, name: &str) -> Result<Candidate> {
+        let candidates = self.search(name).await?;
+        let candidate = candidates
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound(name.to_string()))?;
+        Ok(candidate)
+    }
+
+    async fn fetch_ratings(&self, name: &str, progress: &dyn Progress) -> Result<Ratings> {
+        let candidate = self.best_match(name).await?;
+        self.fetch_ratings_ident(&candidate.id, &candidate.title, progress)
+            .await
+    }
+
+    /// Fetch all seasons, failing if any of them fails
+    async fn fetch_ratings_ident(
+        &self,
+        id: &str,
+        title: &str,
+        progress: &dyn Progress,
+    ) -> Result<Ratings> {
+        let seasons = self.list_seasons(id, title, progress).await?;
+
+        let mut results = Vec::new();
+        for (season, episodes) in self.fetch_seasons(id, seasons, progress).await {
+            results.push(Season {
+                season: SeasonId::from(season.as_str()),
+                episodes: episodes?,
+            });
+        }
+        results.sort_by_key(|s| s.season);
+
+        Ok(Ratings {
+            id: id.to_string(),
+            name: title.to_string(),
+            seasons: results,
+            failed: vec![],
+        })
+    }
+
+    /// Fetch all seasons, keeping the ones that succeed and listing the ones that failed
+    async fn fetch_ratings_partial(
+        &self,
+        id: &str,
+        title: &str,
+        progress: &dyn Progress,
+    ) -> Result<Ratings> {
+        let seasons = self.list_seasons(id, title, progress).await?;
+
+        let mut ratings = Ratings {
+            id: id.to_string(),
+            name: title.to_string(),
+            ..Default::default()
+        };
+        let mut results = self.fetch_seasons(id, seasons, progress).await;
+
+        // Nothing to show, so report why instead
+        if !results.is_empty() && results.iter().all(|(_, episodes)| episodes.is_err()) {
+            let (_, episodes) = results.swap_remove(0);
+            episodes?;
+        }
+
+        add_seasons(&mut ratings, results);
+        Ok(ratings)
+    }
+
+    /// Fetch the seasons that failed earlier again, keeping the ones that succeeded
+    async fn retry_failed(&self, mut ratings: Ratings, progress: &dyn Progress) -> Ratings {
+        let seasons: Vec<_> = std::mem::take(&mut ratings.failed)
+            .into_iter()
+            .map(|f| f.season)
+            .collect();
+        progress.event(ProgressEvent::Seasons {
+            count: seasons.len(),
+        });
+        let results = self.fetch_seasons(&ratings.id, seasons, progress).await;
+        add_seasons(&mut ratings, results);
+        ratings
+    }
+
+    /// List the seasons of a show, reporting the show and the number of seasons
+    async fn list_seasons(
+        &self,
+        id: &str,
+        title: &str,
+        progress: &dyn Progress,
+    ) -> Result<Vec<String>> {
+        progress.event(ProgressEvent::Resolved {
+            id: id.to_string(),
+            title: title.to_string(),
+        });
+
+        let seasons = self.seasons(id).await?;
+        info!("found {} seasons", seasons.len());
+
+        progress.event(ProgressEvent::Seasons {
+            count: seasons.len(),
+        });
+        Ok(seasons)
+    }
+
+    /// Fetch the episodes of several seasons concurrently, in the order they were given
+    async fn fetch_seasons(
+        &self,
+        id: &str,
+        seasons: Vec<String>,
+        progress: &dyn Progress,
+    ) -> Vec<(String, Result<Vec<Episode>>)> {
+        let mut results: Vec<_> = seasons.iter().map(|_| None).collect();
+
+        let mut pending: FuturesUnordered<_> = seasons
+            .into_iter()
+            .enumerate()
+            .map(|(idx, season)| async move {
+                let episodes = self.episodes(id, &season).await;
+                (idx, season, episodes)
+            })
+            .collect();
+
+        while let Some((idx, season, episodes)) = pending.next().await {
+            progress.event(ProgressEvent::SeasonDone {
+                season: season.clone(),
+                ok: episodes.is_ok(),
+            });
+            results[idx] = Some((season, episodes));
+        }
+
+        results.into_iter().flatten().collect()
+    }
+}
+
+/// Add fetched seasons to `ratings`, recording the ones that failed
+fn add_seasons(ratings: &mut Ratings, results: Vec<(String, Result<Vec<Episode>>)>) {
+    for (season, episodes) in results {
+        match episodes {
+            Ok(episodes) => ratings.seasons.push(Season {
+                season: SeasonId::from(season.as_str()),
+                episodes,
+            }),
+            Err(err) => {
+                warn!("season {} of {} failed: {:#}", season, ratings.id, err);
+                ratings.failed.push(SeasonFailure {
+                    season,
+                    error: format!("{:#}", err),
+                });
+            }
+        }
+    }
+    ratings.seasons.sort_by_key(|s| s.season);
+}
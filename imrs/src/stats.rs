@@ -0,0 +1,219 @@
+//! Summary statistics over the episode ratings of a show
+
+use crate::model::{Episode, SeasonId, Show};
+use serde::Serialize;
+
+/// Statistics of a set of ratings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    /// Number of rated episodes
+    pub episodes: usize,
+    pub mean: f32,
+    pub median: f32,
+    /// Population standard deviation
+    pub stdev: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Mean weighted by the number of votes, `None` without vote counts
+    pub weighted_mean: Option<f32>,
+}
+
+impl Summary {
+    /// Summarize the rated episodes, `None` if none of them are rated
+    pub fn new<'a>(episodes: impl IntoIterator<Item = &'a Episode>) -> Option<Summary> {
+        let rated: Vec<_> = episodes
+            .into_iter()
+            .filter_map(|e| Some((e.rating?, e.votes)))
+            .collect();
+        let ratings: Vec<_> = rated.iter().map(|(rating, _)| *rating).collect();
+
+        let votes: u64 = rated
+            .iter()
+            .filter_map(|(_, votes)| *votes)
+            .map(u64::from)
+            .sum();
+        let weighted_mean = (votes > 0).then(|| {
+            let sum: f64 = rated
+                .iter()
+                .filter_map(|(rating, votes)| Some(*rating as f64 * (*votes)? as f64))
+                .sum();
+            (sum / votes as f64) as f32
+        });
+
+        Some(Summary {
+            episodes: ratings.len(),
+            mean: mean(&ratings)?,
+            median: median(&ratings)?,
+            stdev: stdev(&ratings)?,
+            min: ratings.iter().copied().fold(f32::INFINITY, f32::min),
+            max: ratings.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            weighted_mean,
+        })
+    }
+}
+
+/// Statistics of a single season
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeasonStats {
+    pub season: SeasonId,
+    #[serde(flatten)]
+    pub summary: Summary,
+    /// Change in rating per episode over the season
+    pub trend: Option<f32>,
+}
+
+/// An episode picked out by [`Stats`], e.g. the best rated one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Highlight {
+    pub season: SeasonId,
+    pub number: Option<u32>,
+    pub title: String,
+    pub rating: f32,
+    pub votes: Option<u32>,
+}
+
+/// Change in mean rating from one season to the next
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeasonDelta {
+    pub from: SeasonId,
+    pub to: SeasonId,
+    pub delta: f32,
+}
+
+/// Statistics of a whole show
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub id: String,
+    pub name: String,
+    /// Over the regular seasons, `None` if none of their episodes are rated
+    pub overall: Option<Summary>,
+    /// Change in rating per episode over the regular seasons
+    pub trend: Option<f32>,
+    /// Seasons with at least one rated episode
+    pub seasons: Vec<SeasonStats>,
+    /// Best rated episodes of the regular seasons, best first
+    pub best: Vec<Highlight>,
+    /// Worst rated episodes of the regular seasons, worst first
+    pub worst: Vec<Highlight>,
+    /// Between consecutive regular seasons
+    pub deltas: Vec<SeasonDelta>,
+}
+
+impl Stats {
+    /// Compute the statistics of a show, picking the `top` best and worst episodes
+    pub fn new(show: &Show, top: usize) -> Stats {
+        let seasons: Vec<_> = show
+            .seasons
+            .iter()
+            .filter_map(|season| {
+                Some(SeasonStats {
+                    season: season.season,
+                    summary: Summary::new(&season.episodes)?,
+                    trend: trend(&season.episodes),
+                })
+            })
+            .collect();
+
+        let regular: Vec<_> = seasons.iter().filter(|s| s.season.is_regular()).collect();
+        let deltas = regular
+            .windows(2)
+            .map(|pair| SeasonDelta {
+                from: pair[0].season,
+                to: pair[1].season,
+                delta: pair[1].summary.mean - pair[0].summary.mean,
+            })
+            .collect();
+
+        // Specials and episodes without a season would skew the summary, trend and highlights
+        let regular_seasons = || show.seasons.iter().filter(|s| s.season.is_regular());
+        let mut rated: Vec<_> = regular_seasons()
+            .flat_map(|season| {
+                season.episodes.iter().filter_map(|e| {
+                    Some(Highlight {
+                        season: season.season,
+                        number: e.number,
+                        title: e.title.clone(),
+                        rating: e.rating?,
+                        votes: e.votes,
+                    })
+                })
+            })
+            .collect();
+        let episodes: Vec<_> = regular_seasons()
+            .flat_map(|season| season.episodes.iter().cloned())
+            .collect();
+
+        // Stable, so ties keep the airing order
+        rated.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        let best = rated.iter().take(top).cloned().collect();
+        rated.sort_by(|a, b| a.rating.total_cmp(&b.rating));
+        let worst = rated.iter().take(top).cloned().collect();
+
+        Stats {
+            id: show.id.clone(),
+            name: show.name.clone(),
+            overall: Summary::new(&episodes),
+            trend: trend(&episodes),
+            seasons,
+            best,
+            worst,
+            deltas,
+        }
+    }
+}
+
+/// Slope of the least squares line through the ratings of `episodes` in order
+fn trend(episodes: &[Episode]) -> Option<f32> {
+    let points: Vec<_> = episodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| Some((i as f32, e.rating?)))
+        .collect();
+    linear_fit(&points).map(|(slope, _)| slope)
+}
+
+pub fn mean(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f32>() / values.len() as f32)
+}
+
+pub fn median(values: &[f32]) -> Option<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len.is_multiple_of(2) => Some((sorted[mid - 1] + sorted[mid]) / 2.0),
+        _ => Some(sorted[mid]),
+    }
+}
+
+/// Population standard deviation
+pub fn stdev(values: &[f32]) -> Option<f32> {
+    let mean = mean(values)?;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    Some(variance.sqrt())
+}
+
+/// Least squares fit of `y = slope * x + intercept`, `None` for fewer than two distinct x
+pub fn linear_fit(points: &[(f32, f32)]) -> Option<(f32, f32)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f32;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+    let sxx: f32 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f32 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    Some((slope, mean_y - slope * mean_x))
+}
@@ -1,190 +1,294 @@
-use anyhow::{anyhow, Result};
-use regex::Regex;
-use scraper::Node;
+use crate::client::ImdbClient;
+use crate::model::{Crew, Distribution, Episode, Movie, Person, Season, SeasonId, Show};
+use crate::parse;
+use crate::source::{Candidate, RatingsSource};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::try_join_all;
 use std::collections::HashMap;
-use tokio::task::JoinSet;
 use tracing::info;
 
-/*
-TODO:
-- reqwest client reuse
- */
-
-#[derive(Debug, Clone)]
-pub struct Ratings {
-    pub name: String,
-    pub ratings: HashMap<String, Vec<f32>>,
-}
+/// The ratings of a whole show, see [`Show`]
+pub type Ratings = Show;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Todo")]
-    Todo,
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error("{url} returned {status}")]
+    Status {
+        url: String,
+        status: reqwest::StatusCode,
+    },
 
     #[error("Not found: {0}")]
     NotFound(String),
-}
 
-pub async fn fetch_id_and_title(name: &str) -> Result<(String, String)> {
-    let url = format!("https://www.imdb.com/find?q={}&s=tt&ttype=tv", name);
-    let client = reqwest::Client::new();
-    let response = client.get(&url).header("Accept-Language", "en").send().await?;
-    let text = response.text().await?;
+    /// The page did not look like expected, most likely because IMDb changed the markup
+    #[error("Unexpected page layout at {url}: nothing matched {selector}")]
+    Layout { url: String, selector: String },
 
-    let document = scraper::Html::parse_document(&text);
+    /// IMDb answered a GraphQL query with errors instead of data
+    #[error("GraphQL error: {0}")]
+    GraphQl(String),
 
-    let candidate_selector =
-        scraper::Selector::parse(".findResult .result_text a, .find-title-result a").unwrap();
+    #[error("Invalid rating: {0:?}")]
+    ParseRating(String),
 
-    let mut candidates = document.select(&candidate_selector);
+    /// The source does not have this kind of data, e.g. vote histograms in the datasets
+    #[error("Not available from {0}")]
+    Unsupported(&'static str),
+}
 
-    let candidate = candidates
-        .next()
-        .ok_or(Error::NotFound("candidate".to_string()))?;
+impl Error {
+    pub(crate) fn layout(url: &str, selector: &str) -> Error {
+        Error::Layout {
+            url: url.to_string(),
+            selector: selector.to_string(),
+        }
+    }
 
-    let link = candidate.value();
-    let link = link.attr("href").ok_or(anyhow!("no link"))?;
+    /// Record which page a layout error happened on
+    pub(crate) fn at(self, page_url: &str) -> Error {
+        match self {
+            Error::Layout { selector, .. } => Error::layout(page_url, &selector),
+            err => err,
+        }
+    }
+}
 
-    let tt_id_re = Regex::new(r"/title/(.+)/").unwrap();
-    let cap = tt_id_re.captures(link).ok_or(anyhow!("no match"))?;
-    let tt_id = cap.get(1).ok_or(anyhow!("no match"))?.as_str();
+/// Ratings scraped from the imdb.com website
+#[derive(Debug, Clone, Default)]
+pub struct Scraper {
+    client: ImdbClient,
+}
+
+impl Scraper {
+    pub fn new() -> Scraper {
+        Scraper::with_client(ImdbClient::new())
+    }
 
-    let title = candidate.inner_html();
-    info!("tt_id: {}, title: {}", tt_id, title);
-    Ok((tt_id.to_string(), title))
+    pub fn with_client(client: ImdbClient) -> Scraper {
+        Scraper { client }
+    }
 }
 
-async fn fetch_seasons(tt_id: &str) -> Result<Vec<String>> {
-    // Get seasons
-    let url = format!("https://www.imdb.com/title/{}/episodes/", tt_id);
-    let response = reqwest::get(url).await?;
-    let text = response.text().await?;
-    let season_selector = scraper::Selector::parse("[data-testid=\"tab-season-entry\"]").unwrap();
-    let document = scraper::Html::parse_document(&text);
-    let seasons = document.select(&season_selector);
+#[async_trait]
+impl RatingsSource for Scraper {
+    async fn search(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(fetch_candidates(&self.client, name, "tv").await?)
+    }
 
-    let mut res = Vec::new();
+    async fn resolve_id(&self, id: &str) -> Result<Candidate> {
+        let title = fetch_title(&self.client, id).await?;
+        Ok(Candidate {
+            id: id.to_string(),
+            title,
+            ..Default::default()
+        })
+    }
 
-    for season in seasons {
-        let val = season.text().collect::<Vec<_>>().join("");
-        res.push(val);
+    async fn seasons(&self, id: &str) -> Result<Vec<String>> {
+        Ok(fetch_seasons(&self.client, id).await?)
     }
-    return Ok(res);
-}
 
-async fn fetch_season_ratings(tt_id: &str, season: &str) -> Result<Vec<f32>> {
-    info!("Fetch ratings for season {}", season);
+    async fn episodes(&self, id: &str, season: &str) -> Result<Vec<Episode>> {
+        Ok(fetch_season_episodes(&self.client, id, season).await?)
+    }
 
-    let mut season_ratings = Vec::new();
-
-    // Get rating
-    let url = format!(
-        "https://www.imdb.com/title/{}/episodes/?season={}",
-        tt_id, season
-    );
-    let response = reqwest::get(url).await?;
-    let text = response.text().await?;
-    let document = scraper::Html::parse_document(&text);
-
-    let rating_group_container_selector =
-        scraper::Selector::parse("[data-testid=\"ratingGroup--container\"]").unwrap();
-
-    let rating_group_containers = document.select(&rating_group_container_selector);
-    for (_idx, row) in rating_group_containers.enumerate() {
-        match row.first_child() {
-            None => {
-                info!("No ratings");
-                season_ratings.push(-1.0);
-                continue;
-            }
-            Some(span_ch) => {
-                let rating = span_ch
-                    .first_child()
-                    .unwrap()
-                    .next_sibling()
-                    .unwrap()
-                    .value();
-                let ep_rating: &str = rating.as_text().unwrap();
-                let ep_rating: f32 = ep_rating.parse()?;
-
-                season_ratings.push(ep_rating);
-            }
-        }
+    async fn search_movies(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(fetch_candidates(&self.client, name, "ft").await?)
     }
-    // remove -1.0 suffix
-    if let Some(idx) = season_ratings.iter().position(|&r| r == -1.0) {
-        let suffix = season_ratings[idx..].to_vec();
-        if suffix.iter().all(|&r| r == -1.0) {
-            season_ratings.truncate(idx);
-        }
+
+    async fn movie(&self, id: &str) -> Result<Movie> {
+        Ok(fetch_movie(&self.client, id).await?)
     }
 
-    Ok(season_ratings)
+    async fn search_people(&self, name: &str) -> Result<Vec<Candidate>> {
+        let url = self
+            .client
+            .url(&format!("/find?q={}&s=nm", urlencoding::encode(name)));
+        let text = self.client.get_text(&url).await?;
+        Ok(parse::parse_search_page(&text).map_err(|e| e.at(&url))?)
+    }
+
+    async fn person(&self, id: &str) -> Result<Person> {
+        Ok(fetch_person(&self.client, id).await?)
+    }
+
+    async fn distribution(&self, id: &str) -> Result<Distribution> {
+        let url = self.client.url(&format!("/title/{}/ratings/", id));
+        let text = self.client.get_text(&url).await?;
+        let distribution = parse::parse_ratings_page(&text).map_err(|e| e.at(&url))?;
+        Ok(Distribution {
+            id: id.to_string(),
+            ..distribution
+        })
+    }
+
+    async fn crew(&self, ids: &[String]) -> Result<HashMap<String, Crew>> {
+        let pages = try_join_all(ids.chunks(CREW_CHUNK).map(|ids| async move {
+            info!("Fetch crew for {} episodes", ids.len());
+            let variables = serde_json::json!({ "ids": ids });
+            let json = self.client.graphql(parse::CREW_QUERY, &variables).await?;
+            parse::parse_crew_query(&json)
+        }))
+        .await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+}
+
+/// Number of episodes to ask for in one [`parse::CREW_QUERY`]
+const CREW_CHUNK: usize = 100;
+
+/// Search for titles of a type, `tv` for series and `ft` for films
+async fn fetch_candidates(
+    client: &ImdbClient,
+    name: &str,
+    ttype: &str,
+) -> Result<Vec<Candidate>, Error> {
+    let url = client.url(&format!(
+        "/find?q={}&s=tt&ttype={}",
+        urlencoding::encode(name),
+        ttype
+    ));
+    let text = client.get_text(&url).await?;
+    parse::parse_search_page(&text).map_err(|e| e.at(&url))
 }
 
-pub async fn fetch_ratings(name: &str) -> Result<Ratings> {
-    let (id, title) = fetch_id_and_title(name).await?;
-    fetch_ratings_ident(&id, &title).await
+async fn fetch_title(client: &ImdbClient, tt_id: &str) -> Result<String, Error> {
+    let url = client.url(&format!("/title/{}/", tt_id));
+    let text = client.get_text(&url).await?;
+    parse::parse_title_page(&text).map_err(|e| e.at(&url))
 }
 
-pub async fn fetch_ratings_ident(id: &str, title: &str) -> Result<Ratings> {
-    let seasons = fetch_seasons(&id).await?;
+async fn fetch_movie(client: &ImdbClient, tt_id: &str) -> Result<Movie, Error> {
+    let url = client.url(&format!("/title/{}/", tt_id));
+    let text = client.get_text(&url).await?;
+    let movie = parse::parse_movie_page(&text).map_err(|e| e.at(&url))?;
+    Ok(Movie {
+        id: tt_id.to_string(),
+        ..movie
+    })
+}
 
-    info!("found {} seasons", seasons.len());
+async fn fetch_person(client: &ImdbClient, nm_id: &str) -> Result<Person, Error> {
+    let mut person = Person {
+        id: nm_id.to_string(),
+        ..Default::default()
+    };
+    let mut after: Option<String> = None;
 
-    let mut results = HashMap::new();
+    loop {
+        info!("Fetch credits for {} after {:?}", nm_id, after);
 
-    let mut set = JoinSet::new();
+        let variables = serde_json::json!({ "const": nm_id, "after": after });
+        let json = client.graphql(parse::CREDITS_QUERY, &variables).await?;
+        let page = parse::parse_credits_query(&json)
+            .map_err(|e| e.at(&client.url(&format!("/name/{}/", nm_id))))?
+            .ok_or_else(|| Error::NotFound(nm_id.to_string()))?;
 
-    for season in seasons {
-        let id = id.to_string();
-        set.spawn(async move {
-            let season_ratings = fetch_season_ratings(&id, &season).await;
-            (season, season_ratings)
-        });
+        person.name = page.name;
+        let done = page.credits.is_empty() || page.next_page.is_none() || page.next_page == after;
+        person.credits.extend(page.credits);
+        if done {
+            break;
+        }
+        after = page.next_page;
     }
-    while let Some(r) = set.join_next().await {
-        let (season, season_ratings_result) = r?;
-        let season_ratings = season_ratings_result?;
-        results.insert(season, season_ratings);
+
+    Ok(person)
+}
+
+async fn fetch_seasons(client: &ImdbClient, tt_id: &str) -> Result<Vec<String>, Error> {
+    let url = client.url(&format!("/title/{}/episodes/", tt_id));
+    let text = client.get_text(&url).await?;
+    parse::parse_seasons_page(&text).map_err(|e| e.at(&url))
+}
+
+async fn fetch_season_episodes(
+    client: &ImdbClient,
+    tt_id: &str,
+    season: &str,
+) -> Result<Vec<Episode>, Error> {
+    info!("Fetch ratings for season {}", season);
+
+    let url = client.url(&format!("/title/{}/episodes/?season={}", tt_id, season));
+    let text = client.get_text(&url).await?;
+    let page = parse::parse_episodes_page(&text).map_err(|e| e.at(&url))?;
+
+    let mut episodes = page.episodes;
+    let mut next_page = page.next_page;
+
+    // Long seasons only show the first episodes, the rest are loaded with "more" buttons
+    while let Some(after) = next_page.take() {
+        info!("Fetch more episodes for season {} after {}", season, after);
+
+        let variables = serde_json::json!({ "const": tt_id, "season": season, "after": after });
+        let json = client.graphql(parse::EPISODES_QUERY, &variables).await?;
+        let page = parse::parse_episodes_query(&json).map_err(|e| e.at(&url))?;
+
+        let done = page.episodes.is_empty() || page.next_page.as_ref() == Some(&after);
+        episodes.extend(page.episodes);
+        if done {
+            break;
+        }
+        next_page = page.next_page;
     }
 
-    Ok(Ratings {
-        name: title.to_string(),
-        ratings: results,
-    })
+    Ok(episodes)
 }
 
 pub fn test_ratings() -> Ratings {
-    let mut result: HashMap<String, Vec<f32>> = HashMap::new();
-    result.insert("1".to_string(), vec![9.0, 8.6, 8.7, 8.2, 8.3, 9.3, 8.8]);
-    result.insert(
-        "2".to_string(),
-        vec![
-            8.6, 9.3, 8.3, 8.2, 8.3, 8.8, 8.6, 9.2, 9.1, 8.4, 8.9, 9.3, 9.2,
-        ],
-    );
-    result.insert(
-        "3".to_string(),
-        vec![
-            8.5, 8.6, 8.4, 8.2, 8.5, 9.3, 9.6, 8.7, 8.4, 7.9, 8.4, 9.5, 9.7,
-        ],
-    );
-    result.insert(
-        "4".to_string(),
-        vec![
-            9.2, 8.2, 8.0, 8.6, 8.6, 8.4, 8.8, 9.3, 8.8, 9.6, 9.7, 9.5, 9.9,
-        ],
-    );
-    result.insert(
-        "5".to_string(),
-        vec![
-            9.2, 8.8, 8.8, 8.8, 9.7, 9.0, 9.5, 9.6, 9.4, 9.2, 9.6, 9.1, 9.8, 10.0, 9.7, 9.9,
-        ],
-    );
+    let result: Vec<(&str, Vec<f32>)> = vec![
+        ("1", vec![9.0, 8.6, 8.7, 8.2, 8.3, 9.3, 8.8]),
+        (
+            "2",
+            vec![
+                8.6, 9.3, 8.3, 8.2, 8.3, 8.8, 8.6, 9.2, 9.1, 8.4, 8.9, 9.3, 9.2,
+            ],
+        ),
+        (
+            "3",
+            vec![
+                8.5, 8.6, 8.4, 8.2, 8.5, 9.3, 9.6, 8.7, 8.4, 7.9, 8.4, 9.5, 9.7,
+            ],
+        ),
+        (
+            "4",
+            vec![
+                9.2, 8.2, 8.0, 8.6, 8.6, 8.4, 8.8, 9.3, 8.8, 9.6, 9.7, 9.5, 9.9,
+            ],
+        ),
+        (
+            "5",
+            vec![
+                9.2, 8.8, 8.8, 8.8, 9.7, 9.0, 9.5, 9.6, 9.4, 9.2, 9.6, 9.1, 9.8, 10.0, 9.7, 9.9,
+            ],
+        ),
+    ];
+
+    let seasons = result
+        .into_iter()
+        .map(|(season, ratings)| Season {
+            season: SeasonId::from(season),
+            episodes: ratings
+                .into_iter()
+                .enumerate()
+                .map(|(i, rating)| Episode {
+                    number: Some(i as u32 + 1),
+                    rating: Some(rating),
+                    ..Default::default()
+                })
+                .collect(),
+        })
+        .collect();
 
     Ratings {
+        id: "tt0903747".to_string(),
         name: "Breaking Bad".to_string(),
-        ratings: result,
+        seasons,
+        failed: vec![],
     }
 }
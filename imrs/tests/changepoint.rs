@@ -0,0 +1,58 @@
+use imrs::changepoint::{detect, jumped_the_shark, Options};
+use imrs::model::{Episode, Season, SeasonId, Show};
+
+fn show(seasons: &[&[f32]]) -> Show {
+    Show {
+        seasons: seasons
+            .iter()
+            .enumerate()
+            .map(|(s, ratings)| Season {
+                season: SeasonId::Number(s as u32 + 1),
+                episodes: ratings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, rating)| Episode {
+                        number: Some(i as u32 + 1),
+                        rating: Some(*rating),
+                        ..Default::default()
+                    })
+                    .collect(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sustained_drop() {
+    let good = [8.6, 8.9, 8.7, 9.0, 8.8, 8.5, 8.9, 8.7];
+    let bad = [7.2, 7.0, 7.4, 6.9, 7.1, 7.3, 7.0, 7.2];
+    let mut show = show(&[&good, &good, &bad]);
+    // an unrated episode at the end of a season is not plotted, so it does not move the marker
+    show.seasons[1].episodes.push(Episode::default());
+
+    let changes = detect(&show, &Options::default());
+    assert_eq!(changes.len(), 1);
+
+    let change = &changes[0];
+    assert_eq!(change.position, 0);
+    assert_eq!(change.season, SeasonId::Number(3));
+    assert_eq!(change.number, Some(1));
+    // right after the 16 plotted episodes of the first two seasons, x counting from 1
+    assert_eq!(change.marker().x(&show.data()), Some(17));
+    assert!(change.shift() < -1.5);
+    assert!(change.confidence > 0.99);
+
+    assert_eq!(jumped_the_shark(&show), Some(change.clone()));
+}
+
+#[test]
+fn noise_is_not_a_change() {
+    let ratings = [8.1, 8.4, 7.9, 8.3, 8.0, 8.2, 8.5, 7.8, 8.1, 8.3, 8.0, 8.2];
+    assert!(detect(&show(&[&ratings, &ratings]), &Options::default()).is_empty());
+
+    // a single bad episode is not sustained
+    let mut ratings = ratings;
+    ratings[6] = 4.0;
+    assert!(detect(&show(&[&ratings]), &Options::default()).is_empty());
+}
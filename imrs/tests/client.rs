@@ -0,0 +1,146 @@
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use imrs::client::ImdbClient;
+use imrs::tvshow::Error;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Responses to return in order, repeating the last one, and the number of requests so far
+#[derive(Clone)]
+struct Replies {
+    replies: Arc<Vec<(StatusCode, Option<&'static str>)>>,
+    requests: Arc<AtomicUsize>,
+}
+
+async fn reply(State(replies): State<Replies>) -> (StatusCode, HeaderMap, &'static str) {
+    let n = replies.requests.fetch_add(1, Ordering::SeqCst);
+    let (status, retry_after) = replies.replies[n.min(replies.replies.len() - 1)];
+    let mut headers = HeaderMap::new();
+    if let Some(retry_after) = retry_after {
+        headers.insert("Retry-After", retry_after.parse().unwrap());
+    }
+    (status, headers, "body")
+}
+
+async fn serve(replies: &[(StatusCode, Option<&'static str>)]) -> (ImdbClient, Arc<AtomicUsize>) {
+    let replies = Replies {
+        replies: Arc::new(replies.to_vec()),
+        requests: Arc::default(),
+    };
+    let requests = replies.requests.clone();
+    let app = Router::new().route("/", get(reply)).with_state(replies);
+
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+    let addr: SocketAddr = server.local_addr();
+    tokio::spawn(server);
+
+    let url = format!("http://{}", addr);
+    let client = ImdbClient::new()
+        .with_urls(&url, &url)
+        .with_retries(3, Duration::from_millis(20));
+    (client, requests)
+}
+
+#[tokio::test]
+async fn retries_server_errors_with_backoff() {
+    let (client, requests) = serve(&[
+        (StatusCode::SERVICE_UNAVAILABLE, None),
+        (StatusCode::INTERNAL_SERVER_ERROR, None),
+        (StatusCode::OK, None),
+    ])
+    .await;
+
+    let start = Instant::now();
+    let body = client.get_text(&client.url("/")).await.unwrap();
+    assert_eq!(body, "body");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+    // 20ms and then 40ms
+    assert!(start.elapsed() >= Duration::from_millis(60));
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let (client, requests) = serve(&[(StatusCode::BAD_GATEWAY, None)]).await;
+
+    let err = client.get_text(&client.url("/")).await.unwrap_err();
+    assert!(matches!(err, Error::Status { status, .. } if status == StatusCode::BAD_GATEWAY));
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let (client, requests) = serve(&[(StatusCode::NOT_FOUND, None)]).await;
+
+    let err = client.get_text(&client.url("/")).await.unwrap_err();
+    assert!(matches!(err, Error::Status { status, .. } if status == StatusCode::NOT_FOUND));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn waits_for_retry_after() {
+    let (client, requests) = serve(&[
+        (StatusCode::TOO_MANY_REQUESTS, Some("1")),
+        (StatusCode::OK, None),
+    ])
+    .await;
+
+    let start = Instant::now();
+    client.get_text(&client.url("/")).await.unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    // The header wins over the much shorter backoff
+    assert!(start.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn long_retry_after_gives_up() {
+    let (client, requests) = serve(&[(StatusCode::TOO_MANY_REQUESTS, Some("3600"))]).await;
+
+    let start = Instant::now();
+    let err = client.get_text(&client.url("/")).await.unwrap_err();
+    assert!(matches!(err, Error::Status { status, .. } if status == StatusCode::TOO_MANY_REQUESTS));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert!(start.elapsed() < ImdbClient::MAX_RETRY_AFTER);
+}
+
+#[tokio::test]
+async fn backoff_is_capped() {
+    let (client, requests) = serve(&[(StatusCode::INTERNAL_SERVER_ERROR, None)]).await;
+    // Doubling 40 times would overflow without the cap
+    let client = client
+        .with_retries(40, Duration::from_millis(1))
+        .with_max_backoff(Duration::from_millis(2));
+
+    let start = Instant::now();
+    client.get_text(&client.url("/")).await.unwrap_err();
+    assert_eq!(requests.load(Ordering::SeqCst), 41);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn other_requests_go_through_during_retry_after() {
+    let (client, requests) = serve(&[
+        (StatusCode::TOO_MANY_REQUESTS, Some("1")),
+        (StatusCode::OK, None),
+    ])
+    .await;
+    let client = Arc::new(client.with_max_concurrent_requests(1));
+
+    let waiting = tokio::spawn({
+        let client = client.clone();
+        async move { client.get_text(&client.url("/")).await }
+    });
+    while requests.load(Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // The only permit is free while the first request waits for its Retry-After
+    let start = Instant::now();
+    client.get_text(&client.url("/")).await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    waiting.await.unwrap().unwrap();
+}
@@ -0,0 +1,61 @@
+use imrs::dataset::Dataset;
+use imrs::model::SeasonId;
+use imrs::source::RatingsSource;
+
+fn dataset() -> Dataset {
+    Dataset::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/dataset"
+    ))
+    .unwrap()
+}
+
+#[test]
+fn find_exact_matches_first_then_by_votes() {
+    let dataset = dataset();
+
+    let ids: Vec<_> = dataset
+        .find("the office")
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    assert_eq!(ids, ["tt0000002", "tt0000001"]);
+
+    let found = dataset.find("office");
+    let ids: Vec<_> = found.iter().map(|c| c.id.as_str()).collect();
+    // Only series, the film is not a match
+    assert_eq!(ids, ["tt0000002", "tt0000001", "tt0000003"]);
+    assert_eq!(found[0].years.as_deref(), Some("2005–2013"));
+    assert_eq!(found[2].years.as_deref(), Some("2019"));
+    assert_eq!(found[2].kind.as_deref(), Some("TV Mini Series"));
+
+    assert!(dataset.find("parks").is_empty());
+}
+
+#[tokio::test]
+async fn episodes_grouped_by_season() {
+    let dataset = dataset();
+    let show = dataset
+        .fetch_ratings_ident("tt0000002", "The Office", &())
+        .await
+        .unwrap();
+
+    let seasons: Vec<_> = show.seasons.iter().map(|s| s.season).collect();
+    assert_eq!(
+        seasons,
+        [SeasonId::Number(1), SeasonId::Number(2), SeasonId::Unknown]
+    );
+
+    // In episode order, whatever the order of the rows
+    let first = &show.seasons[0].episodes;
+    let titles: Vec<_> = first.iter().map(|e| e.title.as_str()).collect();
+    assert_eq!(titles, ["Pilot", "Diversity Day", "Health Care"]);
+    assert_eq!(first[1].rating, Some(8.1));
+    assert_eq!(first[1].votes, Some(8000));
+    assert_eq!(first[2].rating, None);
+
+    let unknown = &show.seasons[2].episodes;
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].number, None);
+    assert_eq!(unknown[0].title, "Webisode");
+}
@@ -0,0 +1,104 @@
+use imrs::crew::{self, Role};
+use imrs::model::{Distribution, Episode, Season, SeasonId, Show};
+
+#[test]
+fn season_ids_are_ordered_numerically() {
+    let mut seasons: Vec<SeasonId> = ["Unknown", "10", "2", "Specials", "1"]
+        .into_iter()
+        .map(SeasonId::from)
+        .collect();
+    seasons.sort();
+
+    assert_eq!(
+        seasons,
+        [
+            SeasonId::Number(1),
+            SeasonId::Number(2),
+            SeasonId::Number(10),
+            SeasonId::Specials,
+            SeasonId::Unknown,
+        ]
+    );
+}
+
+#[test]
+fn season_ids_from_imdb_names() {
+    assert_eq!(SeasonId::from("0"), SeasonId::Specials);
+    assert_eq!(SeasonId::from(" 3 "), SeasonId::Number(3));
+    assert_eq!(SeasonId::from("Unknown"), SeasonId::Unknown);
+    assert_eq!(SeasonId::from(7).to_string(), "7");
+    assert_eq!(
+        serde_json::to_string(&SeasonId::Specials).unwrap(),
+        "\"Specials\""
+    );
+}
+
+#[test]
+fn regular_seasons_drop_specials_and_unknown() {
+    let season = |season| Season {
+        season,
+        episodes: vec![Episode::default()],
+    };
+    let show = Show {
+        seasons: vec![
+            season(SeasonId::Number(1)),
+            season(SeasonId::Specials),
+            season(SeasonId::Unknown),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(show.data().len(), 3);
+
+    let show = show.regular_seasons();
+    let seasons: Vec<_> = show.seasons.iter().map(|s| s.season).collect();
+    assert_eq!(seasons, [SeasonId::Number(1)]);
+}
+
+#[test]
+fn distribution_spread() {
+    let uniform = Distribution {
+        votes: [0, 0, 0, 0, 10, 10, 0, 0, 0, 0],
+        ..Default::default()
+    };
+    let polarizing = Distribution {
+        votes: [10, 0, 0, 0, 0, 0, 0, 0, 0, 10],
+        ..Default::default()
+    };
+
+    assert_eq!(uniform.mean(), Some(5.5));
+    assert_eq!(polarizing.mean(), Some(5.5));
+    assert_eq!(uniform.stdev(), Some(0.5));
+    assert_eq!(polarizing.stdev(), Some(4.5));
+
+    assert_eq!(Distribution::default().mean(), None);
+}
+
+#[test]
+fn group_ratings_by_director() {
+    let episode = |rating, directors: &[&str]| Episode {
+        rating,
+        directors: directors.iter().map(|d| d.to_string()).collect(),
+        ..Default::default()
+    };
+    let show = Show {
+        seasons: vec![Season {
+            season: SeasonId::Number(1),
+            episodes: vec![
+                episode(Some(8.0), &["A"]),
+                episode(Some(9.0), &["B"]),
+                episode(Some(7.0), &["A", "B"]),
+                episode(None, &["C"]),
+            ],
+        }],
+        ..Default::default()
+    };
+
+    let groups = crew::group_by(&show, Role::Director);
+    let names: Vec<_> = groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, ["B", "A"]);
+    assert_eq!(groups[0].ratings, [9.0, 7.0]);
+    assert_eq!(groups[1].mean, 7.5);
+
+    assert!(crew::group_by(&show, Role::Writer).is_empty());
+}
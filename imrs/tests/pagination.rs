@@ -0,0 +1,138 @@
+use axum::extract::Query;
+use axum::response::Html;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use imrs::client::ImdbClient;
+use imrs::source::RatingsSource;
+use imrs::tvshow::Scraper;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+const SHOW: &str = "tt0000001";
+/// Show whose last page points back at itself instead of ending
+const REPEATING_SHOW: &str = "tt0000002";
+
+fn episode(number: u32) -> (String, String) {
+    (format!("tt10000{:02}", number), number.to_string())
+}
+
+/// Season page with the first three episodes, like imdb.com renders for long seasons
+async fn season_page(Query(query): Query<HashMap<String, String>>) -> Html<String> {
+    let items: Vec<_> = if query.contains_key("season") {
+        (1..=3)
+            .map(episode)
+            .map(|(id, number)| {
+                json!({
+                    "id": id,
+                    "season": "1",
+                    "episode": number,
+                    "titleText": format!("Episode #1.{}", number),
+                    "aggregateRating": 7.0,
+                    "voteCount": 100,
+                })
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let data = json!({
+        "props": { "pageProps": { "contentData": { "section": {
+            "seasons": [{ "value": "1", "text": "1" }],
+            "episodes": { "items": items, "total": 8, "hasNextPage": true, "endCursor": "cursor-3" },
+        }}}}
+    });
+
+    Html(format!(
+        r#"<html><body><script id="__NEXT_DATA__" type="application/json">{}</script></body></html>"#,
+        data
+    ))
+}
+
+/// The GraphQL endpoint behind the "more" button, three episodes at a time
+async fn graphql(Json(body): Json<Value>) -> Json<Value> {
+    let variables = &body["variables"];
+    assert!(variables["const"] == SHOW || variables["const"] == REPEATING_SHOW);
+    assert_eq!(variables["season"], "1");
+    let repeating = variables["const"] == REPEATING_SHOW;
+
+    let (numbers, next) = match variables["after"].as_str() {
+        Some("cursor-3") => (4..=6, Some("cursor-6")),
+        Some("cursor-6") => (7..=8, Some("cursor-6").filter(|_| repeating)),
+        other => panic!("unexpected cursor {:?}", other),
+    };
+
+    let edges: Vec<_> = numbers
+        .map(|number| {
+            json!({ "node": {
+                "id": episode(number).0,
+                "titleText": { "text": format!("Episode #1.{}", number) },
+                "releaseDate": { "day": number, "month": 1, "year": 2020 },
+                "ratingsSummary": { "aggregateRating": 8.0, "voteCount": 50 },
+                "series": { "episodeNumber": { "episodeNumber": number } },
+            }})
+        })
+        .collect();
+
+    Json(json!({ "data": { "title": { "episodes": { "episodes": {
+        "edges": edges,
+        "pageInfo": { "hasNextPage": next.is_some(), "endCursor": next },
+    }}}}}))
+}
+
+async fn serve() -> SocketAddr {
+    let app = Router::new()
+        .route("/title/:id/episodes/", get(season_page))
+        .route("/graphql", post(graphql));
+
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+fn scraper(addr: SocketAddr) -> Scraper {
+    let client = ImdbClient::new().with_urls(
+        &format!("http://{}", addr),
+        &format!("http://{}/graphql", addr),
+    );
+    Scraper::with_client(client)
+}
+
+#[tokio::test]
+async fn follows_season_pagination() {
+    let source = scraper(serve().await);
+
+    let show = source
+        .fetch_ratings_ident(SHOW, "Long Show", &())
+        .await
+        .unwrap();
+
+    assert_eq!(show.seasons.len(), 1);
+    let episodes = &show.seasons[0].episodes;
+    let numbers: Vec<_> = episodes.iter().map(|e| e.number.unwrap()).collect();
+    assert_eq!(numbers, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert_eq!(episodes[0].rating, Some(7.0));
+    assert_eq!(episodes[7].rating, Some(8.0));
+    assert_eq!(episodes[7].votes, Some(50));
+    assert_eq!(episodes[7].title, "Episode #1.8");
+}
+
+#[tokio::test]
+async fn keeps_last_page_with_repeated_cursor() {
+    let source = scraper(serve().await);
+
+    let show = source
+        .fetch_ratings_ident(REPEATING_SHOW, "Long Show", &())
+        .await
+        .unwrap();
+
+    let numbers: Vec<_> = show.seasons[0]
+        .episodes
+        .iter()
+        .map(|e| e.number.unwrap())
+        .collect();
+    assert_eq!(numbers, [1, 2, 3, 4, 5, 6, 7, 8]);
+}
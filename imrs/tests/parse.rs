@@ -0,0 +1,250 @@
+use chrono::NaiveDate;
+use imrs::parse::{
+    parse_credits_query, parse_crew_query, parse_episodes_page, parse_episodes_query,
+    parse_movie_page, parse_ratings_page, parse_search_page, parse_seasons_page, parse_title_page,
+    EPISODE_SELECTOR, LINKED_DATA_SELECTOR, SEASON_SELECTOR,
+};
+use imrs::source::is_ambiguous;
+use imrs::tvshow::Error;
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+#[test]
+fn search_page() {
+    let candidates = parse_search_page(&fixture("search.html")).unwrap();
+
+    let ids: Vec<_> = candidates.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["tt0386676", "tt0290978", "tt1182341"]);
+
+    assert_eq!(candidates[0].title, "The Office");
+    assert_eq!(candidates[0].years.as_deref(), Some("2005–2013"));
+    assert_eq!(candidates[0].kind.as_deref(), Some("TV Series"));
+    assert_eq!(candidates[2].kind.as_deref(), Some("TV Episode"));
+
+    assert!(is_ambiguous(&candidates));
+}
+
+#[test]
+fn search_page_without_results() {
+    let candidates = parse_search_page(&fixture("search_empty.html")).unwrap();
+    assert!(candidates.is_empty());
+}
+
+#[test]
+fn search_page_with_people() {
+    let candidates = parse_search_page(&fixture("search_names.html")).unwrap();
+
+    let ids: Vec<_> = candidates.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["nm0319213", "nm4271336"]);
+    assert_eq!(candidates[0].title, "Vince Gilligan");
+    assert_eq!(candidates[0].kind.as_deref(), Some("Writer"));
+    assert_eq!(candidates[0].years, None);
+}
+
+#[test]
+fn credits_query() {
+    let page = parse_credits_query(&fixture("credits.json"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(page.name, "Vince Gilligan");
+    assert_eq!(page.next_page.as_deref(), Some("Y3JlZGl0OjM="));
+
+    let roles: Vec<_> = page.credits.iter().map(|c| c.role.as_str()).collect();
+    assert_eq!(roles, ["writer", "director", "producer"]);
+
+    let movie = &page.credits[1];
+    assert_eq!(movie.id, "tt9243946");
+    assert_eq!(movie.kind.as_deref(), Some("Movie"));
+    assert_eq!(movie.year, Some(2019));
+    assert_eq!(movie.rating, Some(7.3));
+    assert_eq!(movie.votes, Some(301_234));
+
+    // not rated yet
+    assert_eq!(page.credits[2].rating, None);
+    assert_eq!(page.credits[2].votes, None);
+
+    let missing = parse_credits_query(r#"{"data":{"name":null}}"#).unwrap();
+    assert_eq!(missing, None);
+}
+
+#[test]
+fn crew_query() {
+    let crew = parse_crew_query(&fixture("crew.json")).unwrap();
+    assert_eq!(crew.len(), 2);
+
+    let episode = &crew["tt1054724"];
+    assert_eq!(episode.directors, ["Adam Bernstein"]);
+    assert_eq!(episode.writers, ["Vince Gilligan", "George Mastras"]);
+}
+
+#[test]
+fn episodes_query_with_errors() {
+    let json = r#"{"errors":[{"message":"Rate limited"},{"message":"Try again"}],"data":null}"#;
+    match parse_episodes_query(json) {
+        Err(Error::GraphQl(message)) => assert_eq!(message, "Rate limited; Try again"),
+        other => panic!("expected a GraphQL error, got {:?}", other),
+    }
+
+    // Not a GraphQL error, the layout changed
+    assert!(matches!(
+        parse_episodes_query(r#"{"data":{}}"#),
+        Err(Error::Layout { .. })
+    ));
+}
+
+#[test]
+fn title_page() {
+    let title = parse_title_page(&fixture("title.html")).unwrap();
+    assert_eq!(title, "Breaking Bad");
+}
+
+#[test]
+fn movie_page() {
+    let movie = parse_movie_page(&fixture("movie.html")).unwrap();
+    assert_eq!(movie.title, "Ocean's Eleven");
+    assert_eq!(movie.release_date, NaiveDate::from_ymd_opt(2001, 12, 7));
+    assert_eq!(movie.year, Some(2001));
+    assert_eq!(movie.rating, Some(7.7));
+    assert_eq!(movie.votes, Some(612_345));
+
+    match parse_movie_page(&fixture("title.html")) {
+        Err(Error::Layout { selector, .. }) => assert_eq!(selector, LINKED_DATA_SELECTOR),
+        other => panic!("expected a layout error, got {:?}", other),
+    }
+}
+
+#[test]
+fn ratings_page() {
+    let distribution = parse_ratings_page(&fixture("ratings.html")).unwrap();
+    assert_eq!(distribution.title, "Ozymandias");
+    assert_eq!(distribution.rating, Some(10.0));
+    assert_eq!(distribution.votes[0], 2_100);
+    assert_eq!(distribution.votes[9], 210_000);
+    assert_eq!(distribution.total(), 231_000);
+
+    assert!(matches!(
+        parse_ratings_page(&fixture("title.html")),
+        Err(Error::Layout { .. })
+    ));
+}
+
+#[test]
+fn seasons_page() {
+    let seasons = parse_seasons_page(&fixture("episodes.html")).unwrap();
+    assert_eq!(seasons, ["1", "2", "3", "4", "5"]);
+
+    let seasons = parse_seasons_page(&fixture("episodes_unrated.html")).unwrap();
+    assert_eq!(seasons, ["1", "2", "Unknown"]);
+}
+
+#[test]
+fn episodes_page() {
+    let episodes = parse_episodes_page(&fixture("episodes.html"))
+        .unwrap()
+        .episodes;
+    assert_eq!(episodes.len(), 7);
+
+    let pilot = &episodes[0];
+    assert_eq!(pilot.id.as_deref(), Some("tt0959621"));
+    assert_eq!(pilot.number, Some(1));
+    assert_eq!(pilot.title, "Pilot");
+    assert_eq!(pilot.air_date, NaiveDate::from_ymd_opt(2008, 1, 20));
+    assert_eq!(pilot.rating, Some(9.0));
+    assert_eq!(pilot.votes, Some(32_000));
+
+    assert_eq!(episodes[1].title, "Cat's in the Bag...");
+
+    let ratings: Vec<_> = episodes.iter().map(|e| e.rating.unwrap()).collect();
+    assert_eq!(ratings, [9.0, 8.6, 8.7, 8.2, 8.3, 9.3, 8.8]);
+}
+
+#[test]
+fn episodes_page_with_unrated_unaired_and_missing_episodes() {
+    let episodes = parse_episodes_page(&fixture("episodes_unrated.html"))
+        .unwrap()
+        .episodes;
+
+    let numbers: Vec<_> = episodes.iter().map(|e| e.number.unwrap()).collect();
+    assert_eq!(numbers, [1, 2, 4, 5, 6]);
+
+    assert_eq!(episodes[0].votes, Some(1_200));
+    assert_eq!(episodes[1].votes, Some(853));
+
+    // aired, but not rated yet
+    assert_eq!(episodes[2].rating, None);
+    assert_eq!(episodes[2].votes, None);
+    assert_eq!(episodes[2].air_date, NaiveDate::from_ymd_opt(2024, 9, 24));
+
+    // not aired yet, with and without an air date
+    assert_eq!(episodes[3].rating, None);
+    assert_eq!(episodes[3].air_date, NaiveDate::from_ymd_opt(2030, 10, 1));
+    assert_eq!(episodes[4].rating, None);
+    assert_eq!(episodes[4].air_date, None);
+}
+
+#[test]
+fn episodes_page_layout_changed() {
+    let html = fixture("episodes_layout_changed.html");
+
+    match parse_episodes_page(&html) {
+        Err(Error::Layout { selector, .. }) => assert_eq!(selector, EPISODE_SELECTOR),
+        other => panic!("expected a layout error, got {:?}", other),
+    }
+    match parse_seasons_page(&html) {
+        Err(Error::Layout { selector, .. }) => assert_eq!(selector, SEASON_SELECTOR),
+        other => panic!("expected a layout error, got {:?}", other),
+    }
+}
+
+#[test]
+fn episodes_page_next_data() {
+    let html = fixture("episodes_next_data.html");
+
+    let seasons = parse_seasons_page(&html).unwrap();
+    assert_eq!(seasons, ["1", "2", "3", "4", "5"]);
+
+    let page = parse_episodes_page(&html).unwrap();
+    assert_eq!(page.next_page, None);
+
+    let episodes = page.episodes;
+    assert_eq!(episodes.len(), 7);
+
+    let pilot = &episodes[0];
+    assert_eq!(pilot.id.as_deref(), Some("tt0959621"));
+    assert_eq!(pilot.number, Some(1));
+    assert_eq!(pilot.title, "Pilot");
+    assert_eq!(pilot.air_date, NaiveDate::from_ymd_opt(2008, 1, 20));
+    assert_eq!(pilot.rating, Some(9.0));
+    // exact counts instead of the abbreviated "32K" in the markup
+    assert_eq!(pilot.votes, Some(32_456));
+}
+
+#[test]
+fn episodes_page_next_data_unrated() {
+    let episodes = parse_episodes_page(&fixture("episodes_unrated_next_data.html"))
+        .unwrap()
+        .episodes;
+
+    let numbers: Vec<_> = episodes.iter().map(|e| e.number.unwrap()).collect();
+    assert_eq!(numbers, [1, 4, 5, 6]);
+
+    assert_eq!(episodes[0].votes, Some(1_187));
+    for episode in &episodes[1..] {
+        assert_eq!(episode.rating, None);
+        assert_eq!(episode.votes, None);
+    }
+    // only the year is known
+    assert_eq!(episodes[2].air_date, None);
+}
+
+#[test]
+fn episodes_page_invalid_next_data_falls_back_to_markup() {
+    let html = fixture("episodes_next_data.html").replace("\"props\":", "\"props\"");
+
+    let episodes = parse_episodes_page(&html).unwrap().episodes;
+    assert_eq!(episodes.len(), 7);
+    assert_eq!(episodes[0].votes, Some(32_000));
+}
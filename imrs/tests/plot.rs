@@ -0,0 +1,278 @@
+use imrs::plot::{self, ChartKind, ColorScale, Format, LegendPosition, Overlays, PlotOptions};
+use plotters::style::RGBColor;
+use std::path::Path;
+
+#[test]
+fn format_from_path() {
+    assert_eq!(Format::from_path(Path::new("chart.svg")), Some(Format::Svg));
+    assert_eq!(
+        Format::from_path(Path::new("out/chart.PNG")),
+        Some(Format::Png)
+    );
+    assert_eq!(Format::from_path(Path::new("chart")), None);
+    assert!("gif".parse::<Format>().is_err());
+}
+
+#[test]
+fn render_to_memory() {
+    let show = imrs::tvshow::test_ratings();
+    // No spaces in titles or legends, as font-kit trips a debug assertion on blank glyphs
+    let options = |format| PlotOptions {
+        width: 300,
+        height: 200,
+        format,
+        title: "{title}".to_string(),
+        legend: LegendPosition::Hidden,
+        ..Default::default()
+    };
+    let render = |format| {
+        plot::render_plot("Test", show.data(), &Overlays::default(), &options(format)).unwrap()
+    };
+
+    assert!(render(Format::Png).starts_with(b"\x89PNG"));
+    assert!(render(Format::Jpeg).starts_with(&[0xff, 0xd8]));
+    assert!(render(Format::WebP).starts_with(b"RIFF"));
+    assert!(render(Format::Svg).starts_with(b"<svg"));
+}
+
+#[test]
+fn heatmap_cells() {
+    let data: plot::Data = [
+        (1.into(), vec![8.0.into(), None.into(), 9.0.into()]),
+        (2.into(), vec![7.5.into()]),
+    ]
+    .into_iter()
+    .collect();
+    let options = PlotOptions {
+        width: 600,
+        height: 300,
+        format: Format::Svg,
+        title: "{title}".to_string(),
+        ..Default::default()
+    };
+    let svg = plot::render_heatmap("Test", &data, ColorScale::RedGreen, &options).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+
+    for label in ["8.0", "9.0", "7.5", "-"] {
+        assert!(svg.contains(&format!(">\n{label}\n<")), "missing {label}");
+    }
+    // Only the cells of the four episodes, nothing for the end of the short season
+    assert_eq!(svg.matches("<rect").count() - 1, 2 * 4);
+
+    assert_eq!(ColorScale::RedGreen.color(0.0), RGBColor(223, 32, 32));
+    assert_eq!(
+        "viridis".parse::<ColorScale>().unwrap(),
+        ColorScale::Viridis
+    );
+    assert_eq!("heatmap".parse::<ChartKind>().unwrap(), ChartKind::Heatmap);
+}
+
+#[test]
+fn heatmap_cells_by_episode_number() {
+    let point = |number, rating| plot::Point {
+        rating: Some(rating),
+        votes: None,
+        number: Some(number),
+    };
+    // Episode 3 of the first season is missing
+    let data: plot::Data = [
+        (1.into(), vec![point(1, 8.0), point(2, 8.5), point(4, 9.0)]),
+        (2.into(), vec![point(2, 7.5)]),
+    ]
+    .into_iter()
+    .collect();
+    let options = PlotOptions {
+        width: 600,
+        height: 300,
+        format: Format::Svg,
+        title: "{title}".to_string(),
+        ..Default::default()
+    };
+    let svg = plot::render_heatmap("Test", &data, ColorScale::RedGreen, &options).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+
+    // Left edges of the filled cells, after the background
+    let cells: Vec<u32> = svg
+        .lines()
+        .filter(|line| line.starts_with("<rect") && line.contains(r#"stroke="none""#))
+        .skip(1)
+        .map(|line| line.split('"').nth(1).unwrap().parse().unwrap())
+        .collect();
+    let [one, two, four, second_two] = cells[..] else {
+        panic!("expected four cells, got {cells:?}");
+    };
+    // An empty column where episode 3 would be, and the second season starts at episode 2
+    let width = two - one;
+    assert!((four - two).abs_diff(2 * width) <= 2, "{cells:?}");
+    assert_eq!(second_two, two);
+}
+
+#[test]
+fn heatmap_duplicate_episode_numbers() {
+    let point = |number, rating| plot::Point {
+        rating: Some(rating),
+        votes: None,
+        number: Some(number),
+    };
+    // A two part episode listed twice as episode 2
+    let data: plot::Data = [(
+        1.into(),
+        vec![point(1, 8.0), point(2, 6.0), point(2, 9.0), point(3, 7.0)],
+    )]
+    .into_iter()
+    .collect();
+    let options = PlotOptions {
+        width: 600,
+        height: 300,
+        format: Format::Svg,
+        title: "{title}".to_string(),
+        ..Default::default()
+    };
+    let svg = plot::render_heatmap("Test", &data, ColorScale::RedGreen, &options).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+
+    for label in ["8.0", "6.0", "9.0", "7.0"] {
+        assert!(svg.contains(&format!(">\n{label}\n<")), "missing {label}");
+    }
+    let cells: Vec<u32> = svg
+        .lines()
+        .filter(|line| line.starts_with("<rect") && line.contains(r#"stroke="none""#))
+        .skip(1)
+        .map(|line| line.split('"').nth(1).unwrap().parse().unwrap())
+        .collect();
+    // Every episode keeps its own cell, in order, with the later ones moved along
+    assert_eq!(cells.len(), 4, "{cells:?}");
+    assert!(cells.windows(2).all(|pair| pair[0] < pair[1]), "{cells:?}");
+}
+
+#[test]
+fn season_box_plot() {
+    let data: plot::Data = [
+        (
+            1.into(),
+            vec![8.0.into(), 8.5.into(), 9.0.into(), 7.5.into()],
+        ),
+        (2.into(), vec![None.into(), None.into()]),
+        (3.into(), vec![6.0.into(), 7.0.into(), 6.5.into()]),
+    ]
+    .into_iter()
+    .collect();
+    let options = PlotOptions {
+        width: 600,
+        height: 300,
+        format: Format::Svg,
+        title: "{title}".to_string(),
+        ..Default::default()
+    };
+    let render = |violin| {
+        let svg = plot::render_season_box_plot("Test", &data, violin, &options).unwrap();
+        String::from_utf8(svg).unwrap()
+    };
+
+    let boxes = render(false);
+    assert!(boxes.contains(">\n3\n<"));
+    // Season 2 has no rated episodes, so no box either
+    assert!(!boxes.contains(">\n2\n<"));
+    assert!(!boxes.contains("<polygon"));
+
+    let violins = render(true);
+    assert_eq!(violins.matches("<polygon").count(), 2);
+    assert_eq!("violin".parse::<ChartKind>().unwrap(), ChartKind::Violin);
+}
+
+#[test]
+fn trend_line() {
+    // One season drawn from x = 4, ending with an unrated episode
+    let season = [(4, Some(7.0)), (5, None), (6, Some(8.0)), (7, None)];
+    let [start, end] = plot::trend_line(&season).unwrap();
+    assert_eq!(start, (4, 7.0));
+    assert_eq!(end, (7, 8.5));
+
+    // The whole show from x = 1, across seasons
+    let show = [
+        (1, None),
+        (2, Some(9.0)),
+        (3, Some(8.0)),
+        (4, Some(7.0)),
+        (5, Some(6.0)),
+    ];
+    assert_eq!(plot::trend_line(&show), Some([(1, 10.0), (5, 6.0)]));
+
+    // Nothing to fit with fewer than two rated points
+    assert_eq!(plot::trend_line(&[(1, Some(8.0)), (2, None)]), None);
+}
+
+#[test]
+fn compact_legend() {
+    let seasons = |count: u32| -> plot::Data {
+        (1..=count)
+            .map(|season| (season.into(), vec![8.0.into(), 8.5.into()]))
+            .collect()
+    };
+    let render = |data: &plot::Data, legend| {
+        let options = PlotOptions {
+            width: 800,
+            height: 400,
+            format: Format::Svg,
+            title: "{title}".to_string(),
+            legend,
+            ..Default::default()
+        };
+        let svg = plot::render_plot("Test", data.clone(), &Overlays::default(), &options);
+        String::from_utf8(svg.unwrap()).unwrap()
+    };
+
+    let long = seasons(25);
+    for position in ["upper-right", "right", "below"] {
+        let svg = render(&long, position.parse().unwrap());
+        assert!(svg.contains(">\nS1\n<"), "no legend {position}");
+        assert!(svg.contains(">\nS25\n<"), "no legend {position}");
+    }
+    assert!(!render(&long, LegendPosition::Hidden).contains(">\nS1\n<"));
+
+    // Shorter shows spell the seasons out, and the legend stays inside the plotting area
+    let short = seasons(3);
+    for position in ["upper-left", "upper-right", "lower-left", "lower-right"] {
+        let svg = render(&short, position.parse().unwrap());
+        assert!(svg.contains(">\nSeason 1\n<"), "no legend {position}");
+        assert!(svg.contains(">\nSeason 3\n<"), "no legend {position}");
+        assert!(!svg.contains(">\nS1\n<"), "compact legend {position}");
+
+        let (left, top, right, bottom) = plotting_area(&svg);
+        let legend = svg
+            .lines()
+            .find(|line| line.starts_with("<rect") && line.contains(r#"opacity="0.8""#))
+            .unwrap();
+        let (x, y) = (attr(legend, "x"), attr(legend, "y"));
+        let (width, height) = (attr(legend, "width"), attr(legend, "height"));
+        assert!(x > left && x + width < right, "{position}: {legend}");
+        assert!(y > top && y + height < bottom, "{position}: {legend}");
+    }
+}
+
+/// Integer attribute of an SVG element, e.g. `x="70"`
+fn attr(element: &str, name: &str) -> i32 {
+    let start = element.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+    let end = start + element[start..].find('"').unwrap();
+    element[start..end].parse().unwrap()
+}
+
+/// Left, top, right and bottom of the plotting area, from the mesh lines and the x axis
+fn plotting_area(svg: &str) -> (i32, i32, i32, i32) {
+    let mesh: Vec<_> = svg
+        .lines()
+        .filter(|line| line.starts_with(r#"<line opacity="0.2""#))
+        .collect();
+    let left = attr(mesh[0], "x1");
+    let right = attr(mesh[0], "x2");
+    let top = mesh.iter().map(|line| attr(line, "y1")).min().unwrap();
+    // The x axis is the polyline along the whole width, `points="70,330 769,330 "`
+    let start = format!(r#"points="{left},"#);
+    let axis = svg
+        .lines()
+        .find(|line| line.contains(&start) && line.contains(&format!(" {right},")))
+        .unwrap();
+    let after = &axis[axis.find(&start).unwrap() + start.len()..];
+    let bottom = after[..after.find(' ').unwrap()].parse().unwrap();
+    (left, top, right, bottom)
+}
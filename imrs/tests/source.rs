@@ -0,0 +1,218 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use imrs::model::{Episode, Movie, SeasonId};
+use imrs::source::{Candidate, ProgressEvent, RatingsSource};
+use imrs::tvshow::Error;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Source where some seasons fail until they are fixed
+#[derive(Debug, Default)]
+struct Flaky {
+    seasons: Vec<&'static str>,
+    movies: Vec<Movie>,
+    broken: Mutex<HashSet<&'static str>>,
+    fetched: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl RatingsSource for Flaky {
+    async fn search(&self, _name: &str) -> Result<Vec<Candidate>> {
+        Ok(vec![])
+    }
+
+    async fn resolve_id(&self, id: &str) -> Result<Candidate> {
+        Err(anyhow!("unknown id {}", id))
+    }
+
+    async fn seasons(&self, _id: &str) -> Result<Vec<String>> {
+        Ok(self.seasons.iter().map(|s| s.to_string()).collect())
+    }
+
+    async fn episodes(&self, _id: &str, season: &str) -> Result<Vec<Episode>> {
+        self.fetched.lock().unwrap().push(season.to_string());
+        if self.broken.lock().unwrap().contains(season) {
+            return Err(anyhow!("season {} is broken", season));
+        }
+        Ok(vec![Episode {
+            number: Some(1),
+            rating: Some(8.0),
+            ..Default::default()
+        }])
+    }
+
+    async fn search_movies(&self, name: &str) -> Result<Vec<Candidate>> {
+        Ok(self
+            .movies
+            .iter()
+            .filter(|m| m.title == name)
+            .map(|m| Candidate {
+                id: m.id.clone(),
+                title: m.title.clone(),
+                ..Default::default()
+            })
+            .collect())
+    }
+
+    async fn movie(&self, id: &str) -> Result<Movie> {
+        let movie = self.movies.iter().find(|m| m.id == id);
+        movie.cloned().ok_or_else(|| anyhow!("unknown id {}", id))
+    }
+}
+
+fn flaky(seasons: &[&'static str], broken: &[&'static str]) -> Flaky {
+    Flaky {
+        seasons: seasons.to_vec(),
+        broken: Mutex::new(broken.iter().copied().collect()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn partial_ratings_keep_successful_seasons() {
+    let source = flaky(&["1", "2", "3"], &["2"]);
+
+    assert!(source
+        .fetch_ratings_ident("tt1", "Show", &())
+        .await
+        .is_err());
+
+    let ratings = source
+        .fetch_ratings_partial("tt1", "Show", &())
+        .await
+        .unwrap();
+    let seasons: Vec<_> = ratings.seasons.iter().map(|s| s.season).collect();
+    assert_eq!(seasons, [SeasonId::Number(1), SeasonId::Number(3)]);
+    assert_eq!(ratings.failed.len(), 1);
+    assert_eq!(ratings.failed[0].season, "2");
+    assert_eq!(ratings.failed[0].error, "season 2 is broken");
+    assert!(!ratings.is_complete());
+    assert_eq!(ratings.title(), "Show (incomplete)");
+}
+
+#[tokio::test]
+async fn retry_only_fetches_failed_seasons() {
+    let source = flaky(&["1", "2", "3"], &["2"]);
+    let ratings = source
+        .fetch_ratings_partial("tt1", "Show", &())
+        .await
+        .unwrap();
+
+    source.broken.lock().unwrap().clear();
+    source.fetched.lock().unwrap().clear();
+
+    let ratings = source.retry_failed(ratings, &()).await;
+    assert_eq!(*source.fetched.lock().unwrap(), ["2"]);
+
+    let seasons: Vec<_> = ratings.seasons.iter().map(|s| s.season).collect();
+    assert_eq!(
+        seasons,
+        [
+            SeasonId::Number(1),
+            SeasonId::Number(2),
+            SeasonId::Number(3)
+        ]
+    );
+    assert!(ratings.is_complete());
+    assert_eq!(ratings.title(), "Show");
+}
+
+#[tokio::test]
+async fn partial_ratings_fail_when_every_season_fails() {
+    let source = flaky(&["1", "2"], &["1", "2"]);
+    let err = source
+        .fetch_ratings_partial("tt1", "Show", &())
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "season 1 is broken");
+}
+
+#[tokio::test]
+async fn progress_reports_each_season() {
+    let source = flaky(&["1", "2"], &["2"]);
+    let events = Mutex::new(vec![]);
+    let progress = |event| events.lock().unwrap().push(event);
+
+    source
+        .fetch_ratings_partial("tt1", "Show", &progress)
+        .await
+        .unwrap();
+
+    let mut events = events.into_inner().unwrap();
+    // seasons finish in any order
+    events[2..].sort_by_key(|e| format!("{:?}", e));
+    assert_eq!(
+        events,
+        [
+            ProgressEvent::Resolved {
+                id: "tt1".to_string(),
+                title: "Show".to_string()
+            },
+            ProgressEvent::Seasons { count: 2 },
+            ProgressEvent::SeasonDone {
+                season: "1".to_string(),
+                ok: true
+            },
+            ProgressEvent::SeasonDone {
+                season: "2".to_string(),
+                ok: false
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn franchise_in_release_order() {
+    let movie = |id: &str, title: &str, date, year| Movie {
+        id: id.to_string(),
+        title: title.to_string(),
+        release_date: date,
+        year,
+        rating: Some(7.0),
+        ..Default::default()
+    };
+    let source = Flaky {
+        movies: vec![
+            movie(
+                "tt1",
+                "Sequel",
+                NaiveDate::from_ymd_opt(2010, 5, 7),
+                Some(2010),
+            ),
+            movie("tt2", "Original", None, Some(2008)),
+            movie("tt3", "Unreleased", None, None),
+        ],
+        ..Default::default()
+    };
+
+    let titles = ["Unreleased", "tt1", "Original"].map(String::from);
+    let franchise = source.fetch_franchise("Films", &titles).await.unwrap();
+
+    let ids: Vec<_> = franchise.movies.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, ["tt2", "tt1", "tt3"]);
+
+    // only films with a release date end up on the chart
+    let dates: Vec<_> = franchise.data().iter().map(|r| r.date).collect();
+    assert_eq!(
+        dates,
+        [
+            NaiveDate::from_ymd_opt(2008, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2010, 5, 7).unwrap()
+        ]
+    );
+
+    let missing = ["Nope".to_string()];
+    assert!(source.fetch_franchise("Films", &missing).await.is_err());
+}
+
+#[tokio::test]
+async fn missing_capabilities_are_unsupported() {
+    let source = flaky(&["1"], &[]);
+
+    let err = source.fetch_person("Vince Gilligan").await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::Unsupported(_))
+    ));
+}
@@ -0,0 +1,98 @@
+use imrs::model::{Episode, Season, SeasonId, Show};
+use imrs::stats::{linear_fit, Stats};
+
+fn season(season: SeasonId, ratings: &[(f32, u32)]) -> Season {
+    Season {
+        season,
+        episodes: ratings
+            .iter()
+            .enumerate()
+            .map(|(i, (rating, votes))| Episode {
+                number: Some(i as u32 + 1),
+                title: format!("Episode {}", i + 1),
+                rating: Some(*rating),
+                votes: Some(*votes),
+                ..Default::default()
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn show_stats() {
+    let mut first = season(SeasonId::Number(1), &[(8.0, 100), (9.0, 300), (7.0, 100)]);
+    first.episodes.push(Episode::default());
+    let show = Show {
+        seasons: vec![
+            first,
+            season(SeasonId::Number(2), &[(6.0, 10), (7.0, 10)]),
+            season(SeasonId::Specials, &[(9.5, 1)]),
+        ],
+        ..Default::default()
+    };
+
+    let stats = Stats::new(&show, 2);
+
+    let first = &stats.seasons[0].summary;
+    assert_eq!(first.episodes, 3);
+    assert_eq!(first.mean, 8.0);
+    assert_eq!(first.median, 8.0);
+    assert_eq!((first.min, first.max), (7.0, 9.0));
+    assert!((first.stdev - 0.816).abs() < 0.001);
+    assert_eq!(first.weighted_mean, Some(8.4));
+
+    // specials are left out of the deltas
+    assert_eq!(stats.deltas.len(), 1);
+    assert_eq!(stats.deltas[0].delta, -1.5);
+
+    // specials are left out of the highlights too
+    let best: Vec<_> = stats.best.iter().map(|e| e.rating).collect();
+    assert_eq!(best, [9.0, 8.0]);
+    let worst: Vec<_> = stats.worst.iter().map(|e| e.rating).collect();
+    assert_eq!(worst, [6.0, 7.0]);
+
+    // and out of the overall summary
+    let overall = stats.overall.unwrap();
+    assert_eq!(overall.episodes, 5);
+    assert_eq!(overall.max, 9.0);
+}
+
+#[test]
+fn trend_lines() {
+    assert_eq!(
+        linear_fit(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]),
+        Some((2.0, 1.0))
+    );
+    assert_eq!(linear_fit(&[(1.0, 1.0)]), None);
+    assert_eq!(linear_fit(&[(1.0, 1.0), (1.0, 2.0)]), None);
+
+    let falling = Show {
+        seasons: vec![season(SeasonId::Number(1), &[(9.0, 1), (8.0, 1), (7.0, 1)])],
+        ..Default::default()
+    };
+    assert_eq!(Stats::new(&falling, 1).trend, Some(-1.0));
+
+    // a highly rated special does not make the show look like it improves
+    let mut with_specials = falling.clone();
+    with_specials
+        .seasons
+        .push(season(SeasonId::Specials, &[(10.0, 1)]));
+    assert_eq!(Stats::new(&with_specials, 1).trend, Some(-1.0));
+}
+
+#[test]
+fn ties_keep_airing_order() {
+    let show = Show {
+        seasons: vec![
+            season(SeasonId::Number(1), &[(7.0, 1), (8.0, 1)]),
+            season(SeasonId::Number(2), &[(7.0, 1), (8.0, 1)]),
+        ],
+        ..Default::default()
+    };
+    let stats = Stats::new(&show, 2);
+
+    let best: Vec<_> = stats.best.iter().map(|e| e.season).collect();
+    assert_eq!(best, [SeasonId::Number(1), SeasonId::Number(2)]);
+    let worst: Vec<_> = stats.worst.iter().map(|e| e.season).collect();
+    assert_eq!(worst, [SeasonId::Number(1), SeasonId::Number(2)]);
+}
@@ -1,14 +1,101 @@
+use crate::api::error::{ApiError, BadRequest};
+use crate::state::{Entry, IdAndTitle};
 use crate::SharedState;
+use anyhow::Result;
 use axum::extract::{Query, State};
 use axum::response::IntoResponse;
 use axum::Json;
+use imrs::source::Progress;
+use imrs::tvshow::Ratings;
 use serde::Deserialize;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::info;
 
+pub mod error;
+pub mod franchise;
+pub mod histogram;
 pub mod image;
+pub mod person;
+pub mod progress;
+pub mod ratings;
 pub mod slack;
+pub mod stats;
+
+/// A TV show picked either by name or by IMDb id
+#[derive(Debug, Deserialize)]
+pub struct TvShow {
+    name: Option<String>,
+    id: Option<String>,
+}
+
+impl TvShow {
+    async fn ident(&self, state: &SharedState) -> Result<IdAndTitle> {
+        match (&self.id, &self.name) {
+            (Some(id), _) => state.read().await.get_by_id(id).await,
+            (None, Some(name)) => state.write().await.get_id_and_title(name).await,
+            (None, None) => Err(BadRequest("either name or id is required").into()),
+        }
+    }
+}
+
+/// Which seasons to include in plots and ratings
+#[derive(Debug, Deserialize)]
+pub struct Seasons {
+    /// Leave out specials and episodes without a season number
+    #[serde(default)]
+    regular_only: bool,
+}
+
+impl Seasons {
+    fn apply(&self, ratings: Ratings) -> Ratings {
+        if self.regular_only {
+            ratings.regular_seasons()
+        } else {
+            ratings
+        }
+    }
+}
+
+/// Look up a TV show, using the cached entry when it is fresh
+async fn lookup(state: &SharedState, show: &TvShow, progress: &dyn Progress) -> Result<Entry> {
+    let ident = show.ident(state).await?;
+
+    // Retry failed seasons without holding the lock, the cached entry stays as it is until done
+    let retry = state.write().await.start_retry(&ident);
+    if let Some(mut entry) = retry {
+        let source = state.read().await.source.clone();
+        entry.ratings = source.retry_failed(entry.ratings, progress).await;
+        state.write().await.finish_retry(&ident, entry);
+    }
+
+    let entry = cached_or_fetch(state, &ident, progress).await?;
+    info!("Entry {:?}", entry);
+    Ok(entry)
+}
+
+/// The cached entry when it is fresh, or else fetch the ratings, keeping the seasons that could
+/// be fetched
+///
+/// The lock is only taken to look up and store the entry, not while fetching.
+async fn cached_or_fetch(
+    state: &SharedState,
+    ident: &IdAndTitle,
+    progress: &dyn Progress,
+) -> Result<Entry> {
+    let (cached, source) = {
+        let state = state.read().await;
+        (state.check(ident).cloned(), state.source.clone())
+    };
+    if let Some(entry) = cached {
+        return Ok(entry);
+    }
+
+    let ratings = source
+        .fetch_ratings_partial(&ident.id, &ident.title, progress)
+        .await?;
+    Ok(state.write().await.insert(ident, ratings).clone())
+}
 
 #[tracing::instrument]
 pub async fn names(State(state): State<SharedState>) -> impl IntoResponse {
@@ -20,6 +107,24 @@ pub async fn names(State(state): State<SharedState>) -> impl IntoResponse {
     Json(names)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Search {
+    name: String,
+}
+
+/// All candidates matching a name, so the user can pick the right one
+#[tracing::instrument]
+pub async fn search(
+    Query(query): Query<Search>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let candidates = {
+        let mut state = state.write().await;
+        state.search(&query.name).await
+    }?;
+    Ok(Json(candidates))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Hello {
     input: Option<String>,
@@ -0,0 +1,62 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use imrs::tvshow;
+use std::fmt;
+use tracing::error;
+
+/// Something wrong with the request itself, e.g. a missing query parameter
+#[derive(Debug)]
+pub struct BadRequest(pub &'static str);
+
+impl fmt::Display for BadRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bad request: {}", self.0)
+    }
+}
+
+impl std::error::Error for BadRequest {}
+
+/// Error returned by the API handlers, mapping scraping errors to matching status codes
+#[derive(Debug)]
+pub struct ApiError(anyhow::Error);
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(err: E) -> Self {
+        ApiError(err.into())
+    }
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        if self.0.is::<BadRequest>() {
+            return StatusCode::BAD_REQUEST;
+        }
+        match self.0.downcast_ref::<tvshow::Error>() {
+            Some(tvshow::Error::NotFound(_)) => StatusCode::NOT_FOUND,
+            Some(tvshow::Error::Unsupported(_)) => StatusCode::NOT_IMPLEMENTED,
+            Some(tvshow::Error::Status { status, .. }) if *status == StatusCode::NOT_FOUND => {
+                StatusCode::NOT_FOUND
+            }
+            Some(tvshow::Error::Status { status, .. })
+                if *status == StatusCode::TOO_MANY_REQUESTS =>
+            {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Some(_) => StatusCode::BAD_GATEWAY,
+            None => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self.0.downcast_ref::<tvshow::Error>() {
+            Some(tvshow::Error::Layout { url, selector }) => {
+                error!(url, selector, "IMDb page layout changed");
+            }
+            _ => error!("request failed: {:#}", self.0),
+        }
+
+        (self.status(), format!("{:#}", self.0)).into_response()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::api::error::ApiError;
+use crate::api::image::{image_response, ImageOptions};
+use crate::SharedState;
+use anyhow::Result;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use imrs::model::Franchise;
+use imrs::plot;
+use serde::Deserialize;
+
+/// Several films, e.g. `?name=Pixar&titles=Toy Story,tt0198781,Cars`
+#[derive(Debug, Deserialize)]
+pub struct FranchiseQuery {
+    /// Name used in the chart title
+    name: String,
+    /// Comma separated film names or IMDb ids
+    titles: String,
+}
+
+async fn lookup(state: &SharedState, query: &FranchiseQuery) -> Result<Franchise> {
+    let titles: Vec<_> = query
+        .titles
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let source = state.read().await.source.clone();
+    source.fetch_franchise(&query.name, &titles).await
+}
+
+/// Chart of the films' ratings by release date
+pub async fn franchise_image(
+    Query(query): Query<FranchiseQuery>,
+    Query(image): Query<ImageOptions>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let franchise = lookup(&state, &query).await?;
+
+    let options = image.options();
+    let bytes = plot::render_release_plot(&franchise.name, &franchise.data(), &options)?;
+
+    Ok(image_response(&options, bytes))
+}
+
+/// The films' ratings and release dates as JSON
+pub async fn franchise_ratings(
+    Query(query): Query<FranchiseQuery>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let franchise = lookup(&state, &query).await?;
+    Ok(Json(franchise))
+}
@@ -0,0 +1,37 @@
+use crate::api::error::{ApiError, BadRequest};
+use crate::api::image::{image_response, ImageOptions};
+use crate::api::TvShow;
+use crate::SharedState;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use imrs::plot;
+use serde::Deserialize;
+
+/// Pick a single episode instead of the whole show, e.g. `&season=5&episode=14`
+#[derive(Debug, Deserialize)]
+pub struct EpisodeQuery {
+    season: Option<String>,
+    episode: Option<u32>,
+}
+
+/// Chart of how many votes each rating from 1 to 10 got
+pub async fn histogram(
+    Query(query): Query<TvShow>,
+    Query(episode): Query<EpisodeQuery>,
+    Query(image): Query<ImageOptions>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let name = query
+        .id
+        .or(query.name)
+        .ok_or(BadRequest("either name or id is required"))?;
+    let episode = episode.season.as_deref().zip(episode.episode);
+
+    let source = state.read().await.source.clone();
+    let distribution = source.fetch_distribution(&name, episode).await?;
+
+    let options = image.options();
+    let bytes = plot::render_histogram_plot(&distribution.title, &distribution.votes, &options)?;
+
+    Ok(image_response(&options, bytes))
+}
@@ -1,58 +1,126 @@
+use crate::api::error::{ApiError, BadRequest};
+use crate::api::{lookup, Seasons, TvShow};
 use crate::SharedState;
 use axum::extract::{Query, State};
 use axum::response::{AppendHeaders, IntoResponse};
-use image::{ImageBuffer, ImageFormat};
-use imrs::plot;
-use plotters::prelude::*;
+use imrs::changepoint::{self, ChangePoint};
+use imrs::plot::{self, ChartKind, ColorScale, Format, LegendPosition, Overlays, PlotOptions};
+use imrs::tvshow::Ratings;
 use serde::Deserialize;
-use std::io::{BufWriter, Cursor};
-use tracing::info;
 
-#[derive(Deserialize)]
-pub struct TvShow {
-    name: String,
+/// How to chart the episode ratings, and what to draw on top of them
+#[derive(Debug, Deserialize)]
+pub struct ChartOptions {
+    /// "line", "heatmap", "box" or "violin", a line chart by default
+    #[serde(default)]
+    chart: ChartKind,
+    /// Heatmap colors, "red-green", "viridis" or "grayscale"
+    #[serde(default)]
+    color_scale: ColorScale,
+    /// Mark where the ratings shifted for good
+    #[serde(default)]
+    change_points: bool,
+    /// Draw a trend line through each season
+    #[serde(default)]
+    season_trends: bool,
+    /// Draw a trend line through the whole show
+    #[serde(default)]
+    show_trend: bool,
 }
 
-pub async fn plot_tvshow(
-    Query(query): Query<TvShow>,
-    State(state): State<SharedState>,
-) -> impl IntoResponse {
-    let name = query.name;
+impl ChartOptions {
+    /// Markers and trend lines are only drawn on line charts
+    fn check(&self) -> Result<(), BadRequest> {
+        let overlays = self.change_points || self.season_trends || self.show_trend;
+        if overlays && self.chart != ChartKind::Line {
+            return Err(BadRequest(
+                "change_points, season_trends and show_trend only work with chart=line",
+            ));
+        }
+        Ok(())
+    }
 
-    let ident = {
-        let mut state = state.write().await;
-        state.get_id_and_title(&name).await
+    fn overlays(&self, ratings: &Ratings) -> Overlays {
+        let mut overlays = Overlays {
+            season_trends: self.season_trends,
+            show_trend: self.show_trend,
+            ..Default::default()
+        };
+        if self.change_points {
+            overlays.markers = changepoint::detect(ratings, &changepoint::Options::default())
+                .iter()
+                .map(ChangePoint::marker)
+                .collect();
+        }
+        overlays
     }
-    .unwrap();
+}
+
+/// Size and format of a chart, e.g. `&width=1600&height=600&format=svg`
+#[derive(Debug, Deserialize)]
+pub struct ImageOptions {
+    width: Option<u32>,
+    height: Option<u32>,
+    /// "png", "jpeg", "webp" or "svg", PNG by default
+    format: Option<Format>,
+    /// Season legend position, e.g. "upper-right", "below" or "none"
+    legend: Option<LegendPosition>,
+}
+
+impl ImageOptions {
+    /// Larger charts are most likely mistakes, and would take a lot of memory to draw
+    const MAX: u32 = 4000;
 
-    let entry = {
-        let mut state = state.write().await;
-        match state.check(&ident) {
-            Some(entry) => entry,
-            None => state.update(&ident).await.unwrap(),
+    pub(crate) fn options(&self) -> PlotOptions {
+        let defaults = PlotOptions::default();
+        PlotOptions {
+            width: self.width.unwrap_or(defaults.width).clamp(1, Self::MAX),
+            height: self.height.unwrap_or(defaults.height).clamp(1, Self::MAX),
+            format: self.format.unwrap_or(defaults.format),
+            legend: self.legend.unwrap_or(defaults.legend),
+            ..defaults
         }
-        .clone()
-    };
-    info!("Entry {:?}", entry);
-    // create plot
-    let results = entry.ratings;
-    // in memory plot
-    let mut buffer = vec![0; 1200 * 400 * 3];
-    {
-        let root = BitMapBackend::with_buffer(&mut buffer, (1200, 400)).into_drawing_area();
-        plot::create_plot_with_backend(&root, &results.name, results.ratings).unwrap();
     }
+}
 
-    // create image
-    let image_buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
-        ImageBuffer::from_vec(1200, 400, buffer).unwrap();
+/// Respond with a chart rendered by one of the `plot::render_*` functions
+pub(crate) fn image_response(options: &PlotOptions, bytes: Vec<u8>) -> impl IntoResponse {
+    (
+        AppendHeaders([("Content-Type", options.format.content_type())]),
+        bytes,
+    )
+}
 
-    // convert to png
-    let mut buffer = BufWriter::new(Cursor::new(Vec::new()));
-    image_buffer
-        .write_to(&mut buffer, ImageFormat::Png)
-        .unwrap();
-    let bytes = buffer.into_inner().unwrap().into_inner();
+pub async fn plot_tvshow(
+    Query(query): Query<TvShow>,
+    Query(seasons): Query<Seasons>,
+    Query(chart): Query<ChartOptions>,
+    Query(image): Query<ImageOptions>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    chart.check()?;
+    let entry = lookup(&state, &query, &()).await?;
+
+    let results = seasons.apply(entry.ratings);
+    let options = image.options();
+    let bytes = match chart.chart {
+        ChartKind::Line => {
+            let overlays = chart.overlays(&results);
+            plot::render_plot(&results.title(), results.data(), &overlays, &options)?
+        }
+        ChartKind::Heatmap => plot::render_heatmap(
+            &results.title(),
+            &results.data(),
+            chart.color_scale,
+            &options,
+        )?,
+        ChartKind::Box | ChartKind::Violin => plot::render_season_box_plot(
+            &results.title(),
+            &results.data(),
+            chart.chart == ChartKind::Violin,
+            &options,
+        )?,
+    };
 
-    (AppendHeaders([("Content-Type", "image/png")]), bytes)
+    Ok(image_response(&options, bytes))
 }
@@ -0,0 +1,28 @@
+use crate::api::error::ApiError;
+use crate::api::image::{image_response, ImageOptions};
+use crate::SharedState;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use imrs::plot;
+use serde::Deserialize;
+
+/// A person picked by name or IMDb id, e.g. `?name=nm0319213`
+#[derive(Debug, Deserialize)]
+pub struct PersonQuery {
+    name: String,
+}
+
+/// Chart of the ratings of someone's credits by year, colored by role
+pub async fn person_image(
+    Query(query): Query<PersonQuery>,
+    Query(image): Query<ImageOptions>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let source = state.read().await.source.clone();
+    let person = source.fetch_person(&query.name).await?;
+
+    let options = image.options();
+    let bytes = plot::render_career_plot(&person.name, &person.data(), &options)?;
+
+    Ok(image_response(&options, bytes))
+}
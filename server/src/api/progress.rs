@@ -0,0 +1,44 @@
+use crate::api::{lookup, TvShow};
+use crate::SharedState;
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::channel::mpsc;
+use futures::{Stream, StreamExt};
+use imrs::source::ProgressEvent;
+use std::convert::Infallible;
+use tracing::error;
+
+/// Fetch a TV show while streaming progress as server-sent events
+///
+/// Sends a `progress` event for each [`ProgressEvent`], then `done` with the title once the
+/// show is cached for `/api/image`, or `failed` with a message. Not `error`, which browsers use
+/// for connection errors.
+pub async fn progress(
+    Query(query): Query<TvShow>,
+    State(state): State<SharedState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::unbounded();
+
+    tokio::spawn(async move {
+        let progress = |event: ProgressEvent| {
+            let event = Event::default().event("progress").json_data(event);
+            let _ = tx.unbounded_send(event);
+        };
+
+        let event = match lookup(&state, &query, &progress).await {
+            Ok(entry) => Event::default()
+                .event("done")
+                .json_data(entry.ratings.title()),
+            Err(err) => {
+                error!("request failed: {:#}", err);
+                Event::default()
+                    .event("failed")
+                    .json_data(format!("{:#}", err))
+            }
+        };
+        let _ = tx.unbounded_send(event);
+    });
+
+    let events = rx.filter_map(|event| async move { event.ok().map(Ok) });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
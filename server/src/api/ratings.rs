@@ -0,0 +1,16 @@
+use crate::api::error::ApiError;
+use crate::api::{lookup, Seasons, TvShow};
+use crate::SharedState;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+
+/// Episode ratings and vote counts for a TV show as JSON
+pub async fn ratings(
+    Query(query): Query<TvShow>,
+    Query(seasons): Query<Seasons>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let entry = lookup(&state, &query, &()).await?;
+    Ok(Json(seasons.apply(entry.ratings)))
+}
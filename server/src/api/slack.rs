@@ -1,7 +1,12 @@
+use crate::api::cached_or_fetch;
+use crate::state::IdAndTitle;
 use crate::SharedState;
+use anyhow::Result;
 use axum::extract::{Query, State};
 use axum::response::IntoResponse;
 use axum::Json;
+use imrs::source::{is_ambiguous, is_title_id, Candidate};
+use imrs::tvshow;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
@@ -43,36 +48,20 @@ pub async fn slack(
     let prefix = opt.url_prefix;
 
     tokio::spawn(async move {
-        let ident = {
-            let mut state = state.write().await;
-            state.get_id_and_title(&query.text).await
-        }
-        .unwrap();
-
-        info!("id: {:?}", ident);
-        {
-            let mut state = state.write().await;
-            let _entry = match state.check(&ident) {
-                Some(entry) => entry,
-                None => state.update(&ident).await.unwrap(),
-            };
-        }
-
-        // send to slack
-        let name = urlencoding::encode(&query.text);
-        info!("encoded: {}", name);
-
-        let client = reqwest::Client::new();
-
-        let m = SlackMessage {
-            response_type: "in_channel".to_string(),
-            text: ident.title,
-            attachments: vec![SlackMessageAttachment {
-                image_url: Some(format!("{}/api/image?name={}", prefix, name)),
-            }],
+        let m = match reply(&state, query.text.trim(), &prefix).await {
+            Ok(m) => m,
+            Err(e) => {
+                error!("Slack lookup failed: {:#}", e);
+                SlackMessage {
+                    response_type: "ephemeral".to_string(),
+                    text: format!("Could not look up \"{}\": {}", query.text.trim(), e),
+                    attachments: vec![],
+                }
+            }
         };
 
         info!("slack response: {:?}", m);
+        let client = reqwest::Client::new();
         let resp = client.post(&query.response_url).json(&m).send().await;
         if let Err(e) = resp {
             error!("Slack error: {}", e);
@@ -84,3 +73,62 @@ pub async fn slack(
         text: "Loading...".to_string(),
     })
 }
+
+/// Look up the show and build the message to send back
+async fn reply(state: &SharedState, text: &str, prefix: &str) -> Result<SlackMessage> {
+    let ident = if is_title_id(text) {
+        let state = state.read().await;
+        state.get_by_id(text).await?
+    } else {
+        let candidates = {
+            let mut state = state.write().await;
+            state.search(text).await
+        }?;
+
+        if is_ambiguous(&candidates) {
+            return Ok(pick_message(text, &candidates));
+        }
+
+        let best = candidates
+            .into_iter()
+            .next()
+            .ok_or(tvshow::Error::NotFound(text.to_string()))?;
+        IdAndTitle {
+            id: best.id,
+            title: best.title,
+        }
+    };
+
+    info!("id: {:?}", ident);
+    cached_or_fetch(state, &ident, &()).await?;
+
+    // send to slack
+    let id = urlencoding::encode(&ident.id);
+    info!("encoded: {}", id);
+
+    Ok(SlackMessage {
+        response_type: "in_channel".to_string(),
+        text: ident.title,
+        attachments: vec![SlackMessageAttachment {
+            image_url: Some(format!("{}/api/image?id={}", prefix, id)),
+        }],
+    })
+}
+
+/// Ask the user to run the command again with the id of the show they meant
+fn pick_message(text: &str, candidates: &[Candidate]) -> SlackMessage {
+    let options: Vec<_> = candidates
+        .iter()
+        .map(|c| format!("• `{}` {}", c.id, c))
+        .collect();
+
+    SlackMessage {
+        response_type: "ephemeral".to_string(),
+        text: format!(
+            "Found several shows called \"{}\", run the command again with one of these ids:\n{}",
+            text,
+            options.join("\n")
+        ),
+        attachments: vec![],
+    }
+}
@@ -0,0 +1,31 @@
+use crate::api::error::ApiError;
+use crate::api::{lookup, Seasons, TvShow};
+use crate::SharedState;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use imrs::stats::Stats;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Number of best and worst episodes to list
+    #[serde(default = "default_top")]
+    top: usize,
+}
+
+fn default_top() -> usize {
+    5
+}
+
+/// Per-season and overall statistics of a TV show's ratings as JSON
+pub async fn stats(
+    Query(query): Query<TvShow>,
+    Query(seasons): Query<Seasons>,
+    Query(options): Query<StatsQuery>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let entry = lookup(&state, &query, &()).await?;
+    let ratings = seasons.apply(entry.ratings);
+    Ok(Json(Stats::new(&ratings, options.top)))
+}
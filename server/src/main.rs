@@ -1,6 +1,12 @@
+use crate::api::franchise::{franchise_image, franchise_ratings};
+use crate::api::histogram::histogram;
 use crate::api::image::plot_tvshow;
+use crate::api::person::person_image;
+use crate::api::progress::progress;
+use crate::api::ratings::ratings;
 use crate::api::slack::slack;
-use crate::api::{hello, names};
+use crate::api::stats::stats;
+use crate::api::{hello, names, search};
 use crate::opt::Opt;
 use crate::state::AppState;
 use axum::body::{boxed, Body};
@@ -8,6 +14,9 @@ use axum::http::{Response, StatusCode};
 use axum::routing::get;
 use axum::Router;
 use clap::Parser;
+use imrs::dataset::Dataset;
+use imrs::source::RatingsSource;
+use imrs::tvshow::Scraper;
 use std::collections::HashMap;
 use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
@@ -36,17 +45,31 @@ async fn main() {
 
     console_subscriber::init();
 
+    let source: Arc<dyn RatingsSource> = match &opt.dataset {
+        Some(dir) => Arc::new(Dataset::open(dir).expect("failed to load dataset")),
+        None => Arc::new(Scraper::new()),
+    };
+
     let shared_state = Arc::new(RwLock::new(AppState {
         entries: HashMap::new(),
         names: HashMap::new(),
         opt: opt.clone(),
+        source,
     }));
 
     let app = Router::new()
         .route("/api/hello", get(hello))
         .route("/api/image", get(plot_tvshow))
+        .route("/api/franchise/image", get(franchise_image))
+        .route("/api/franchise/ratings", get(franchise_ratings))
+        .route("/api/person", get(person_image))
+        .route("/api/histogram", get(histogram))
+        .route("/api/progress", get(progress))
+        .route("/api/ratings", get(ratings))
         .route("/api/slack", get(slack))
+        .route("/api/stats", get(stats))
         .route("/api/names", get(names))
+        .route("/api/search", get(search))
         .with_state(Arc::clone(&shared_state))
         .fallback_service(get(|req| async move {
             match ServeDir::new(&opt.static_dir).oneshot(req).await {
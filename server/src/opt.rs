@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(name = "server", about = "Backend server")]
@@ -18,4 +19,8 @@ pub struct Opt {
     /// The public facing URL prefix for the backend
     #[clap(long, env, default_value = "http://localhost:8080")]
     pub url_prefix: String,
+
+    /// Directory with the IMDb TSV datasets, used instead of scraping imdb.com
+    #[clap(long, env)]
+    pub dataset: Option<PathBuf>,
 }
@@ -1,8 +1,10 @@
 use crate::opt::Opt;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use imrs::source::{Candidate, RatingsSource};
 use imrs::tvshow;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::info;
 
 #[derive(Clone, Debug)]
@@ -11,17 +13,31 @@ pub struct IdAndTitle {
     pub title: String,
 }
 
+/// Minutes to wait before fetching the failed seasons of an entry again
+const RETRY_MINUTES: i64 = 5;
+
 #[derive(Debug, Clone)]
 pub struct Entry {
     pub date: DateTime<Utc>,
+    /// When the failed seasons were last fetched again
+    pub retried: Option<DateTime<Utc>>,
     pub ratings: tvshow::Ratings,
 }
 
+impl Entry {
+    /// Whether some seasons failed and were not fetched recently, counting the first fetch
+    fn should_retry(&self) -> bool {
+        let last = self.retried.unwrap_or(self.date);
+        !self.ratings.is_complete() && (Utc::now() - last).num_minutes() >= RETRY_MINUTES
+    }
+}
+
 #[derive(Debug)]
 pub struct AppState {
     pub entries: HashMap<String, Entry>,
     pub names: HashMap<String, IdAndTitle>,
     pub opt: Opt,
+    pub source: Arc<dyn RatingsSource>,
 }
 
 impl AppState {
@@ -42,21 +58,40 @@ impl AppState {
         None
     }
 
-    /// Update and return an entry
-    pub async fn update(&mut self, ident: &IdAndTitle) -> Result<&Entry> {
-        // TODO: should probably do the update using channels so we don't block while one is updating
-
-        let results = tvshow::fetch_ratings_ident(&ident.id, &ident.title).await?;
-
+    /// Store freshly fetched ratings, replacing any outdated entry
+    pub fn insert(&mut self, ident: &IdAndTitle, ratings: tvshow::Ratings) -> &Entry {
         self.entries.insert(
             ident.id.to_string(),
             Entry {
                 date: Utc::now(),
-                ratings: results,
+                retried: None,
+                ratings,
             },
         );
 
-        Ok(self.entries.get(&ident.id).unwrap())
+        self.entries.get(&ident.id).unwrap()
+    }
+
+    /// Claim the retry of a fresh but incomplete entry, returning a copy to retry on
+    ///
+    /// The retry time is recorded right away, so concurrent requests don't retry too.
+    pub fn start_retry(&mut self, ident: &IdAndTitle) -> Option<Entry> {
+        if !self.check(ident).is_some_and(Entry::should_retry) {
+            return None;
+        }
+        let entry = self.entries.get_mut(&ident.id)?;
+        info!("retrying {} failed seasons", entry.ratings.failed.len());
+        entry.retried = Some(Utc::now());
+        Some(entry.clone())
+    }
+
+    /// Store the result of a retry, unless the entry was replaced in the meantime
+    pub fn finish_retry(&mut self, ident: &IdAndTitle, retried: Entry) {
+        if let Some(entry) = self.entries.get_mut(&ident.id) {
+            if entry.date == retried.date {
+                entry.ratings = retried.ratings;
+            }
+        }
     }
 
     /// Look up the IMDb id and title for a TV Show
@@ -65,10 +100,80 @@ impl AppState {
             return Ok(ident.clone());
         }
 
-        let (id, title) = tvshow::fetch_id_and_title(name).await?;
-        let ident = IdAndTitle { id, title };
+        let candidate = self.source.best_match(name).await?;
+        let ident = IdAndTitle {
+            id: candidate.id,
+            title: candidate.title,
+        };
         self.names.insert(name.to_string(), ident.clone());
 
         Ok(ident)
     }
+
+    /// Search for all candidates matching a name, remembering the best match
+    pub async fn search(&mut self, name: &str) -> Result<Vec<Candidate>> {
+        let candidates = self.source.search(name).await?;
+        if let Some(best) = candidates.first() {
+            self.names.insert(
+                name.to_string(),
+                IdAndTitle {
+                    id: best.id.clone(),
+                    title: best.title.clone(),
+                },
+            );
+        }
+        Ok(candidates)
+    }
+
+    /// Look up the title for an IMDb id, e.g. after picking one of several search results
+    pub async fn get_by_id(&self, id: &str) -> Result<IdAndTitle> {
+        if let Some(entry) = self.entries.get(id) {
+            return Ok(IdAndTitle {
+                id: id.to_string(),
+                title: entry.ratings.name.clone(),
+            });
+        }
+
+        let candidate = self.source.resolve_id(id).await?;
+        Ok(IdAndTitle {
+            id: candidate.id,
+            title: candidate.title,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use imrs::model::SeasonFailure;
+
+    fn incomplete(age: Duration) -> Entry {
+        Entry {
+            date: Utc::now() - age,
+            retried: None,
+            ratings: tvshow::Ratings {
+                failed: vec![SeasonFailure {
+                    season: "2".to_string(),
+                    error: "timed out".to_string(),
+                }],
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn retry_waits_for_the_interval() {
+        // Just fetched, so the failed seasons are not fetched again right away
+        assert!(!incomplete(Duration::zero()).should_retry());
+        assert!(incomplete(Duration::minutes(RETRY_MINUTES)).should_retry());
+
+        let mut entry = incomplete(Duration::hours(1));
+        entry.retried = Some(Utc::now() - Duration::minutes(1));
+        assert!(!entry.should_retry());
+
+        entry.ratings.failed.clear();
+        entry.retried = None;
+        assert!(!entry.should_retry());
+    }
 }